@@ -1,5 +1,3 @@
-use std::time::SystemTime;
-
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::header::{CONTENT_TYPE, DATE};
 
@@ -9,6 +7,17 @@ use crypto::mac::Mac;
 use crypto::sha1::Sha1;
 
 use super::oss::OSS;
+use super::utils::{encode_query, uri_encode};
+
+/// What a presigned url grants: `verb` on `bucket/object`, with the
+/// signed `oss_resources` in its query.
+#[derive(Clone, Copy)]
+pub struct UrlTarget<'r> {
+    pub verb: &'r str,
+    pub bucket: &'r str,
+    pub object: &'r str,
+    pub oss_resources: &'r str,
+}
 
 pub trait Auth {
     fn oss_sign(
@@ -23,15 +32,23 @@ pub trait Auth {
     ) -> String;
     fn oss_sign_url(
         &self,
-        verb: &str,
+        target: &UrlTarget,
         key_id: &str,
         key_secret: &str,
         expires: Option<u64>,
-        bucket: &str,
-        object: &str,
-        oss_resources: &str,
         headers: &HeaderMap,
     ) -> String;
+    /// Presigned `rtmp://` push url of a LiveChannel, `params` being
+    /// extra query params such as `playlistName`.
+    fn oss_sign_rtmp_url(
+        &self,
+        key_id: &str,
+        key_secret: &str,
+        expires: u64,
+        bucket: &str,
+        channel: &str,
+        params: &[(&str, &str)],
+    ) -> String;
 }
 
 impl<'a> Auth for OSS<'a> {
@@ -55,7 +72,7 @@ impl<'a> Auth for OSS<'a> {
             .unwrap_or_default();
         let content_md5 = headers
             .get("Content-MD5")
-            .and_then(|md5| Some(encode(md5.to_str().unwrap_or_default())))
+            .and_then(|md5| Some(md5.to_str().unwrap_or_default()))
             .unwrap_or_default();
 
         let mut oss_headers: Vec<(&HeaderName, &HeaderValue)> = headers
@@ -88,29 +105,26 @@ impl<'a> Auth for OSS<'a> {
     }
     fn oss_sign_url(
         &self,
-        verb: &str,
+        target: &UrlTarget,
         key_id: &str,
         key_secret: &str,
         expires: Option<u64>,
-        bucket: &str,
-        object: &str,
-        oss_resources: &str,
         headers: &HeaderMap,
     ) -> String {
-        let expires = expires.unwrap_or(
-            SystemTime::now()
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .expect("Time went backwards.")
-                .as_secs()
-                + 3600,
-        );
+        let UrlTarget {
+            verb,
+            bucket,
+            object,
+            oss_resources,
+        } = *target;
+        let expires = expires.unwrap_or(self.now().timestamp() as u64 + 3600);
         let content_type = headers
             .get(CONTENT_TYPE)
             .and_then(|c| Some(c.to_str().unwrap_or_default()))
             .unwrap_or_default();
         let content_md5 = headers
             .get("Content-MD5")
-            .and_then(|md5| Some(encode(md5.to_str().unwrap_or_default())))
+            .and_then(|md5| Some(md5.to_str().unwrap_or_default()))
             .unwrap_or_default();
 
         let mut oss_headers: Vec<(&HeaderName, &HeaderValue)> = headers
@@ -139,9 +153,53 @@ impl<'a> Auth for OSS<'a> {
 
         let auth_params = format!(
             "OSSAccessKeyId={}&Expires={}&Signature={}",
-            key_id, expires, sign_str_base64
+            uri_encode(key_id, false),
+            expires,
+            uri_encode(&sign_str_base64, false)
+        );
+        if oss_resources.is_empty() {
+            self.host(bucket, object, &auth_params)
+        } else {
+            let query = format!("{}&{}", encode_query(oss_resources), auth_params);
+            self.host(bucket, object, &query)
+        }
+    }
+    fn oss_sign_rtmp_url(
+        &self,
+        key_id: &str,
+        key_secret: &str,
+        expires: u64,
+        bucket: &str,
+        channel: &str,
+        params: &[(&str, &str)],
+    ) -> String {
+        let mut params = params.to_vec();
+        params.sort();
+        let canonical_params: String = params
+            .iter()
+            .map(|(k, v)| format!("{}:{}\n", k, v))
+            .collect();
+        let sign_str = format!("{}\n{}/{}/{}", expires, canonical_params, bucket, channel);
+
+        let mut hasher = Hmac::new(Sha1::new(), key_secret.as_bytes());
+        hasher.input(sign_str.as_bytes());
+        let sign_str_base64 = encode(hasher.result().code());
+
+        let mut query: String = params
+            .iter()
+            .map(|(k, v)| format!("{}={}&", uri_encode(k, false), uri_encode(v, false)))
+            .collect();
+        query += &format!(
+            "OSSAccessKeyId={}&Expires={}&Signature={}",
+            uri_encode(key_id, false),
+            expires,
+            uri_encode(&sign_str_base64, false)
         );
-        self.host(bucket, object, &auth_params)
+        let url = self.host(bucket, &format!("live/{}", channel), &query);
+        match url.split_once("://") {
+            Some((_, rest)) => format!("rtmp://{}", rest),
+            None => url,
+        }
     }
 }
 
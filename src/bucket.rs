@@ -0,0 +1,552 @@
+use quick_xml::{events::Event, Reader};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH};
+use reqwest::{Method, StatusCode};
+use std::fmt;
+
+use super::errors::{BucketError, Error};
+use super::oss::{SignedRequest, OSS};
+use super::sse::{ServerSideEncryption, SseAlgorithm};
+use super::utils::escape_xml;
+use super::website::WebsiteConfiguration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersioningStatus {
+    Enabled,
+    Suspended,
+}
+
+impl fmt::Display for VersioningStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VersioningStatus::Enabled => write!(f, "Enabled"),
+            VersioningStatus::Suspended => write!(f, "Suspended"),
+        }
+    }
+}
+
+/// Where access logs of a bucket are written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketLogging {
+    /// Bucket receiving the logs, in the same region as the logged one.
+    pub target_bucket: String,
+    /// Prefix of the log objects, may be empty.
+    pub target_prefix: String,
+}
+
+/// Hotlink protection of a bucket, matched against the `Referer` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefererConfiguration {
+    /// Whether requests without `Referer` are allowed.
+    pub allow_empty_referer: bool,
+    /// Whether the query string is ignored when matching.
+    pub allow_truncate_query_string: Option<bool>,
+    /// Whether the path is ignored when matching, requires
+    /// `allow_truncate_query_string`.
+    pub truncate_path: Option<bool>,
+    /// Allowed referers, `*` and `?` are wildcards. Empty allows any.
+    pub referers: Vec<String>,
+    /// Denied referers, checked before `referers`.
+    pub black_referers: Vec<String>,
+}
+
+impl Default for RefererConfiguration {
+    fn default() -> Self {
+        RefererConfiguration {
+            allow_empty_referer: true,
+            allow_truncate_query_string: None,
+            truncate_path: None,
+            referers: vec![],
+            black_referers: vec![],
+        }
+    }
+}
+
+pub trait BucketAPI {
+    /// Returns the versioning state of the bucket, `None` if versioning
+    /// was never enabled.
+    fn get_bucket_versioning(&self) -> Result<Option<VersioningStatus>, Error>;
+    fn put_bucket_versioning(&self, status: VersioningStatus) -> Result<(), Error>;
+    /// Returns the default encryption of new objects, `None` if there is
+    /// no rule.
+    fn get_bucket_encryption(&self) -> Result<Option<ServerSideEncryption>, Error>;
+    fn put_bucket_encryption(&self, encryption: &ServerSideEncryption) -> Result<(), Error>;
+    fn delete_bucket_encryption(&self) -> Result<(), Error>;
+    /// Returns the static website configuration, `None` if hosting is off.
+    fn get_bucket_website(&self) -> Result<Option<WebsiteConfiguration>, Error>;
+    fn put_bucket_website(&self, config: &WebsiteConfiguration) -> Result<(), Error>;
+    fn delete_bucket_website(&self) -> Result<(), Error>;
+    /// Returns where access logs are written, `None` if logging is off.
+    fn get_bucket_logging(&self) -> Result<Option<BucketLogging>, Error>;
+    fn put_bucket_logging(&self, logging: &BucketLogging) -> Result<(), Error>;
+    fn delete_bucket_logging(&self) -> Result<(), Error>;
+    fn get_bucket_referer(&self) -> Result<RefererConfiguration, Error>;
+    /// There is no delete, put `RefererConfiguration::default()` instead.
+    fn put_bucket_referer(&self, config: &RefererConfiguration) -> Result<(), Error>;
+}
+
+impl<'a> BucketAPI for OSS<'a> {
+    fn get_bucket_versioning(&self) -> Result<Option<VersioningStatus>, Error> {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: "",
+            params: "versioning",
+            resources: "versioning",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Bucket(BucketError::GetError {
+                msg: format!(
+                    "can not get bucket versioning, status code: {}",
+                    resp.status()
+                ),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        reader.trim_text(true);
+        let mut status = None;
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) if e.name() == b"Status" => {
+                    status = match reader.read_text(e.name(), &mut Vec::new())?.as_str() {
+                        "Enabled" => Some(VersioningStatus::Enabled),
+                        "Suspended" => Some(VersioningStatus::Suspended),
+                        _ => None,
+                    }
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(status)
+    }
+
+    fn put_bucket_versioning(&self, status: VersioningStatus) -> Result<(), Error> {
+        let body = format!(
+            "<VersioningConfiguration><Status>{}</Status></VersioningConfiguration>",
+            status
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: "",
+            params: "versioning",
+            resources: "versioning",
+            headers,
+            body: Some(body.into_bytes()),
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Bucket(BucketError::PutError {
+                msg: format!(
+                    "can not put bucket versioning, status code: {}",
+                    resp.status()
+                ),
+            }))
+        }
+    }
+
+    fn get_bucket_encryption(&self) -> Result<Option<ServerSideEncryption>, Error> {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: "",
+            params: "encryption",
+            resources: "encryption",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            if status == StatusCode::NOT_FOUND
+                && resp
+                    .text()?
+                    .contains("<Code>NoSuchServerSideEncryptionRule</Code>")
+            {
+                return Ok(None);
+            }
+            return Err(Error::Bucket(BucketError::GetError {
+                msg: format!("can not get bucket encryption, status code: {}", status),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        reader.trim_text(true);
+        let mut algorithm = None;
+        let mut kms_key_id = None;
+        let mut data_encryption = None;
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match e.name() {
+                    b"SSEAlgorithm" => {
+                        let text = reader.read_text(e.name(), &mut Vec::new())?;
+                        algorithm = Some(SseAlgorithm::from(text.as_str()))
+                    }
+                    b"KMSMasterKeyID" => {
+                        kms_key_id = Some(reader.read_text(e.name(), &mut Vec::new())?)
+                    }
+                    b"KMSDataEncryption" => {
+                        let text = reader.read_text(e.name(), &mut Vec::new())?;
+                        data_encryption = Some(SseAlgorithm::from(text.as_str()))
+                    }
+                    _ => (),
+                },
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(algorithm.map(|algorithm| ServerSideEncryption {
+            algorithm,
+            kms_key_id: kms_key_id.filter(|id| !id.is_empty()),
+            data_encryption,
+        }))
+    }
+
+    fn put_bucket_encryption(&self, encryption: &ServerSideEncryption) -> Result<(), Error> {
+        let mut body = format!(
+            "<ServerSideEncryptionRule><ApplyServerSideEncryptionByDefault><SSEAlgorithm>{}</SSEAlgorithm>",
+            encryption.algorithm
+        );
+        if let Some(key_id) = &encryption.kms_key_id {
+            body += &format!("<KMSMasterKeyID>{}</KMSMasterKeyID>", escape_xml(key_id));
+        }
+        if let Some(data_encryption) = &encryption.data_encryption {
+            body += &format!("<KMSDataEncryption>{}</KMSDataEncryption>", data_encryption);
+        }
+        body += "</ApplyServerSideEncryptionByDefault></ServerSideEncryptionRule>";
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: "",
+            params: "encryption",
+            resources: "encryption",
+            headers,
+            body: Some(body.into_bytes()),
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Bucket(BucketError::PutError {
+                msg: format!(
+                    "can not put bucket encryption, status code: {}",
+                    resp.status()
+                ),
+            }))
+        }
+    }
+
+    fn delete_bucket_encryption(&self) -> Result<(), Error> {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::DELETE,
+            bucket: self.bucket(),
+            object: "",
+            params: "encryption",
+            resources: "encryption",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Bucket(BucketError::DeleteError {
+                msg: format!(
+                    "can not delete bucket encryption, status code: {}",
+                    resp.status()
+                ),
+            }))
+        }
+    }
+
+    fn get_bucket_website(&self) -> Result<Option<WebsiteConfiguration>, Error> {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: "",
+            params: "website",
+            resources: "website",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            if status == StatusCode::NOT_FOUND
+                && resp
+                    .text()?
+                    .contains("<Code>NoSuchWebsiteConfiguration</Code>")
+            {
+                return Ok(None);
+            }
+            return Err(Error::Bucket(BucketError::GetError {
+                msg: format!("can not get bucket website, status code: {}", status),
+            }));
+        }
+        Ok(Some(WebsiteConfiguration::from_xml(&resp.text()?)?))
+    }
+
+    fn put_bucket_website(&self, config: &WebsiteConfiguration) -> Result<(), Error> {
+        let body = config.to_xml();
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: "",
+            params: "website",
+            resources: "website",
+            headers,
+            body: Some(body.into_bytes()),
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Bucket(BucketError::PutError {
+                msg: format!("can not put bucket website, status code: {}", resp.status()),
+            }))
+        }
+    }
+
+    fn delete_bucket_website(&self) -> Result<(), Error> {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::DELETE,
+            bucket: self.bucket(),
+            object: "",
+            params: "website",
+            resources: "website",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Bucket(BucketError::DeleteError {
+                msg: format!(
+                    "can not delete bucket website, status code: {}",
+                    resp.status()
+                ),
+            }))
+        }
+    }
+
+    fn get_bucket_logging(&self) -> Result<Option<BucketLogging>, Error> {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: "",
+            params: "logging",
+            resources: "logging",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Bucket(BucketError::GetError {
+                msg: format!("can not get bucket logging, status code: {}", resp.status()),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        reader.trim_text(true);
+        let mut target_bucket = None;
+        let mut target_prefix = String::new();
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match e.name() {
+                    b"TargetBucket" => {
+                        target_bucket = Some(reader.read_text(e.name(), &mut Vec::new())?)
+                    }
+                    b"TargetPrefix" => {
+                        target_prefix = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    _ => (),
+                },
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(target_bucket.map(|target_bucket| BucketLogging {
+            target_bucket,
+            target_prefix,
+        }))
+    }
+
+    fn put_bucket_logging(&self, logging: &BucketLogging) -> Result<(), Error> {
+        let body = format!(
+            "<BucketLoggingStatus><LoggingEnabled><TargetBucket>{}</TargetBucket><TargetPrefix>{}</TargetPrefix></LoggingEnabled></BucketLoggingStatus>",
+            escape_xml(&logging.target_bucket),
+            escape_xml(&logging.target_prefix)
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: "",
+            params: "logging",
+            resources: "logging",
+            headers,
+            body: Some(body.into_bytes()),
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Bucket(BucketError::PutError {
+                msg: format!("can not put bucket logging, status code: {}", resp.status()),
+            }))
+        }
+    }
+
+    fn delete_bucket_logging(&self) -> Result<(), Error> {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::DELETE,
+            bucket: self.bucket(),
+            object: "",
+            params: "logging",
+            resources: "logging",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Bucket(BucketError::DeleteError {
+                msg: format!(
+                    "can not delete bucket logging, status code: {}",
+                    resp.status()
+                ),
+            }))
+        }
+    }
+
+    fn get_bucket_referer(&self) -> Result<RefererConfiguration, Error> {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: "",
+            params: "referer",
+            resources: "referer",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Bucket(BucketError::GetError {
+                msg: format!("can not get bucket referer, status code: {}", resp.status()),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        reader.trim_text(true);
+        let mut config = RefererConfiguration::default();
+        let mut in_blacklist = false;
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match e.name() {
+                    b"AllowEmptyReferer" => {
+                        config.allow_empty_referer =
+                            reader.read_text(e.name(), &mut Vec::new())?.parse()?
+                    }
+                    b"AllowTruncateQueryString" => {
+                        config.allow_truncate_query_string =
+                            Some(reader.read_text(e.name(), &mut Vec::new())?.parse()?)
+                    }
+                    b"TruncatePath" => {
+                        config.truncate_path =
+                            Some(reader.read_text(e.name(), &mut Vec::new())?.parse()?)
+                    }
+                    b"RefererList" => in_blacklist = false,
+                    b"RefererBlacklist" => in_blacklist = true,
+                    b"Referer" => {
+                        let referer = reader.read_text(e.name(), &mut Vec::new())?;
+                        if in_blacklist {
+                            config.black_referers.push(referer)
+                        } else {
+                            config.referers.push(referer)
+                        }
+                    }
+                    _ => (),
+                },
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(config)
+    }
+
+    fn put_bucket_referer(&self, config: &RefererConfiguration) -> Result<(), Error> {
+        let mut body = format!(
+            "<RefererConfiguration><AllowEmptyReferer>{}</AllowEmptyReferer>",
+            config.allow_empty_referer
+        );
+        if let Some(truncate) = config.allow_truncate_query_string {
+            body += &format!(
+                "<AllowTruncateQueryString>{}</AllowTruncateQueryString>",
+                truncate
+            );
+        }
+        if let Some(truncate) = config.truncate_path {
+            body += &format!("<TruncatePath>{}</TruncatePath>", truncate);
+        }
+        body += "<RefererList>";
+        for referer in &config.referers {
+            body += &format!("<Referer>{}</Referer>", escape_xml(referer));
+        }
+        body += "</RefererList>";
+        if !config.black_referers.is_empty() {
+            body += "<RefererBlacklist>";
+            for referer in &config.black_referers {
+                body += &format!("<Referer>{}</Referer>", escape_xml(referer));
+            }
+            body += "</RefererBlacklist>";
+        }
+        body += "</RefererConfiguration>";
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: "",
+            params: "referer",
+            resources: "referer",
+            headers,
+            body: Some(body.into_bytes()),
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Bucket(BucketError::PutError {
+                msg: format!("can not put bucket referer, status code: {}", resp.status()),
+            }))
+        }
+    }
+}
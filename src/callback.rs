@@ -0,0 +1,439 @@
+//! Upload callbacks: after a put or a completed multipart upload OSS posts
+//! to an application server and relays its response.
+//!
+//! `Callback` builds the `x-oss-callback` and `x-oss-callback-var` headers,
+//! `CallbackVerifier` checks, on the application server, that a callback
+//! request was signed by OSS.
+
+use base64::{decode, encode};
+use crypto::digest::Digest;
+use crypto::md5::Md5;
+use num_bigint::BigUint;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::errors::{Error, ObjectError};
+use super::multipart::UploadedPart;
+use super::object::PutOptions;
+use super::oss::OSS;
+use super::transport::Response;
+use super::utils::percent_decode;
+
+pub const OSS_CALLBACK: &str = "x-oss-callback";
+pub const OSS_CALLBACK_VAR: &str = "x-oss-callback-var";
+/// Header of callback requests holding the base64 url of the public key.
+pub const OSS_PUB_KEY_URL: &str = "x-oss-pub-key-url";
+
+/// Public keys are only fetched from here, and always over https.
+const PUB_KEY_HOST: &str = "gosspublic.alicdn.com/";
+
+/// DER `DigestInfo` prefix of an MD5 hash, as signed by PKCS#1 v1.5.
+const MD5_DIGEST_INFO: &[u8] = &[
+    0x30, 0x20, 0x30, 0x0c, 0x06, 0x08, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x02, 0x05, 0x05, 0x00,
+    0x04, 0x10,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallbackBodyType {
+    /// `application/x-www-form-urlencoded`, the default.
+    FormUrlEncoded,
+    /// `application/json`.
+    Json,
+}
+
+impl CallbackBodyType {
+    fn as_str(self) -> &'static str {
+        match self {
+            CallbackBodyType::FormUrlEncoded => "application/x-www-form-urlencoded",
+            CallbackBodyType::Json => "application/json",
+        }
+    }
+}
+
+/// The request OSS sends once an upload is done. The body is a template in
+/// which `${bucket}`, `${object}`, `${etag}`, `${size}`, `${mimeType}` and
+/// the custom `${x:name}` variables are substituted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Callback {
+    url: String,
+    host: Option<String>,
+    body: String,
+    body_type: CallbackBodyType,
+    sni: bool,
+    vars: Vec<(String, String)>,
+}
+
+impl Callback {
+    /// Posts `body` to `url`, several urls can be given separated by `;`.
+    pub fn new<S1, S2>(url: S1, body: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        Callback {
+            url: url.into(),
+            host: None,
+            body: body.into(),
+            body_type: CallbackBodyType::FormUrlEncoded,
+            sni: false,
+            vars: vec![],
+        }
+    }
+
+    /// `Host` header of the callback request, the url's host by default.
+    pub fn host<S: Into<String>>(mut self, host: S) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    pub fn body_type(mut self, body_type: CallbackBodyType) -> Self {
+        self.body_type = body_type;
+        self
+    }
+
+    /// Sends SNI when calling back over https.
+    pub fn sni(mut self, sni: bool) -> Self {
+        self.sni = sni;
+        self
+    }
+
+    /// Custom variable, referenced as `${x:name}` in the body. The `x:`
+    /// prefix is added when missing.
+    pub fn var<S1, S2>(mut self, name: S1, value: S2) -> Self
+    where
+        S1: AsRef<str>,
+        S2: Into<String>,
+    {
+        let name = name.as_ref();
+        let name = if name.starts_with("x:") {
+            name.to_owned()
+        } else {
+            format!("x:{}", name)
+        };
+        self.vars.push((name, value.into()));
+        self
+    }
+
+    /// Adds the callback headers, see also `PutOptions::with_callback`.
+    pub fn to_headers(&self, headers: &mut HeaderMap) -> Result<(), Error> {
+        let mut callback = format!(
+            "{{\"callbackUrl\":{},\"callbackBody\":{},\"callbackBodyType\":{},\"callbackSNI\":{}",
+            json_string(&self.url),
+            json_string(&self.body),
+            json_string(self.body_type.as_str()),
+            self.sni
+        );
+        if let Some(host) = &self.host {
+            callback += &format!(",\"callbackHost\":{}", json_string(host));
+        }
+        callback += "}";
+        headers.insert(OSS_CALLBACK, encode(&callback).parse()?);
+
+        if !self.vars.is_empty() {
+            let vars = self
+                .vars
+                .iter()
+                .map(|(name, value)| format!("{}:{}", json_string(name), json_string(value)))
+                .collect::<Vec<_>>()
+                .join(",");
+            headers.insert(OSS_CALLBACK_VAR, encode(format!("{{{}}}", vars)).parse()?);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> PutOptions<'a> {
+    /// Has OSS call back once the object is stored. Use
+    /// `CallbackAPI::put_with_callback` to get the callback's response.
+    pub fn with_callback(mut self, callback: &Callback) -> Result<Self, Error> {
+        callback.to_headers(&mut self.headers)?;
+        Ok(self)
+    }
+}
+
+pub trait CallbackAPI {
+    /// Puts the object and returns the body the callback server answered.
+    fn put_with_callback<'a, S, O>(
+        &self,
+        buf: &[u8],
+        object_name: S,
+        callback: &Callback,
+        opts: O,
+    ) -> Result<Vec<u8>, Error>
+    where
+        S: AsRef<str>,
+        O: Into<Option<&'a PutOptions<'a>>>;
+    /// Completes the upload and returns the body the callback server
+    /// answered.
+    fn complete_multipart_upload_with_callback<S1, S2>(
+        &self,
+        object_name: S1,
+        upload_id: S2,
+        parts: &[UploadedPart],
+        callback: &Callback,
+    ) -> Result<Vec<u8>, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>;
+}
+
+impl<'a> CallbackAPI for OSS<'a> {
+    fn put_with_callback<'b, S, O>(
+        &self,
+        buf: &[u8],
+        object_name: S,
+        callback: &Callback,
+        opts: O,
+    ) -> Result<Vec<u8>, Error>
+    where
+        S: AsRef<str>,
+        O: Into<Option<&'b PutOptions<'b>>>,
+    {
+        let opts = match opts.into() {
+            Some(opts) => opts.clone(),
+            None => PutOptions::default(),
+        };
+        let opts = opts.with_callback(callback)?;
+        callback_body(self.put_object(buf, object_name.as_ref(), Some(&opts))?)
+    }
+
+    fn complete_multipart_upload_with_callback<S1, S2>(
+        &self,
+        object_name: S1,
+        upload_id: S2,
+        parts: &[UploadedPart],
+        callback: &Callback,
+    ) -> Result<Vec<u8>, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        let mut headers = HeaderMap::new();
+        callback.to_headers(&mut headers)?;
+        let resp = self.send_complete_multipart_upload(
+            object_name.as_ref(),
+            upload_id.as_ref(),
+            parts,
+            headers,
+        )?;
+        callback_body(resp)
+    }
+}
+
+/// OSS answers 203 when the object was stored but the callback failed.
+fn callback_body(resp: Response) -> Result<Vec<u8>, Error> {
+    if resp.status() == StatusCode::NON_AUTHORITATIVE_INFORMATION {
+        return Err(Error::Object(ObjectError::CallbackError {
+            msg: format!("callback failed: {}", resp.text()?),
+        }));
+    }
+    resp.bytes()
+}
+
+/// Checks the signature of callback requests, on the application server.
+///
+/// OSS signs `{url-decoded path}[?{query}]\n{body}` with RSA and MD5, and
+/// names the public key in the `x-oss-pub-key-url` header. Keys are fetched
+/// once per url, only from the OSS public key host and only over https.
+#[derive(Debug, Default)]
+pub struct CallbackVerifier {
+    fixed_key: Option<Arc<RsaPublicKey>>,
+    keys: Mutex<HashMap<String, Arc<RsaPublicKey>>>,
+}
+
+impl CallbackVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Verifies with the given PEM public key, ignoring `x-oss-pub-key-url`.
+    pub fn with_public_key(pem: &str) -> Result<Self, Error> {
+        Ok(CallbackVerifier {
+            fixed_key: Some(Arc::new(RsaPublicKey::from_pem(pem)?)),
+            keys: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Whether `authorization` is a valid signature of the request. `path`
+    /// is the raw request path and `query` the raw query string without
+    /// `?`, empty when there is none. `pub_key_url` and `authorization`
+    /// are the header values as received.
+    pub fn verify(
+        &self,
+        pub_key_url: &str,
+        authorization: &str,
+        path: &str,
+        query: &str,
+        body: &[u8],
+    ) -> Result<bool, Error> {
+        let signature =
+            decode(authorization).map_err(|_| callback_error("invalid authorization"))?;
+        let key = match &self.fixed_key {
+            Some(key) => key.clone(),
+            None => self.public_key(pub_key_url)?,
+        };
+
+        let mut content = percent_decode(path).into_bytes();
+        if !query.is_empty() {
+            content.push(b'?');
+            content.extend_from_slice(query.as_bytes());
+        }
+        content.push(b'\n');
+        content.extend_from_slice(body);
+        Ok(key.verify_md5(&content, &signature))
+    }
+
+    fn public_key(&self, pub_key_url: &str) -> Result<Arc<RsaPublicKey>, Error> {
+        let url = decode(pub_key_url)
+            .ok()
+            .and_then(|url| String::from_utf8(url).ok())
+            .ok_or_else(|| callback_error("invalid x-oss-pub-key-url"))?;
+        // OSS may still announce the key over http, fetch it over https
+        let url = match ["https://", "http://"]
+            .iter()
+            .find_map(|scheme| url.strip_prefix(scheme))
+            .and_then(|rest| rest.strip_prefix(PUB_KEY_HOST))
+        {
+            Some(path) => format!("https://{}{}", PUB_KEY_HOST, path),
+            None => return Err(callback_error(&format!("untrusted public key url {}", url))),
+        };
+        if let Some(key) = self.keys.lock().unwrap().get(&url) {
+            return Ok(key.clone());
+        }
+        let pem = reqwest::blocking::get(&url)?.error_for_status()?.text()?;
+        let key = Arc::new(RsaPublicKey::from_pem(&pem)?);
+        self.keys.lock().unwrap().insert(url, key.clone());
+        Ok(key)
+    }
+}
+
+/// The modulus and exponent of an RSA public key.
+#[derive(Debug)]
+pub(crate) struct RsaPublicKey {
+    pub(crate) n: BigUint,
+    pub(crate) e: BigUint,
+}
+
+impl RsaPublicKey {
+    /// Reads `PUBLIC KEY` (SubjectPublicKeyInfo) and `RSA PUBLIC KEY`
+    /// (PKCS#1) PEM documents.
+    pub(crate) fn from_pem(pem: &str) -> Result<Self, Error> {
+        let encoded = pem
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with("-----"))
+            .collect::<String>();
+        let der = decode(&encoded).map_err(|_| callback_error("invalid public key pem"))?;
+        parse_public_key(&der).ok_or_else(|| callback_error("invalid public key"))
+    }
+
+    /// Length of the modulus in bytes.
+    pub(crate) fn size(&self) -> usize {
+        self.n.to_bytes_be().len()
+    }
+
+    /// Checks an RSASSA-PKCS1-v1_5 signature over the MD5 of `content`.
+    fn verify_md5(&self, content: &[u8], signature: &[u8]) -> bool {
+        let s = BigUint::from_bytes_be(signature);
+        if s >= self.n {
+            return false;
+        }
+        let m = s.modpow(&self.e, &self.n).to_bytes_be();
+        let expected = md5_encoded(content, self.size());
+        // to_bytes_be drops the leading zero byte of the encoding
+        expected.len() == m.len() + 1 && expected[1..] == m[..]
+    }
+}
+
+/// EMSA-PKCS1-v1_5 encoding of the MD5 of `content`, `len` bytes long.
+pub(crate) fn md5_encoded(content: &[u8], len: usize) -> Vec<u8> {
+    let mut md5 = Md5::new();
+    md5.input(content);
+    let mut digest = [0u8; 16];
+    md5.result(&mut digest);
+
+    let padding = len.saturating_sub(3 + MD5_DIGEST_INFO.len() + digest.len());
+    let mut encoded = vec![0x00, 0x01];
+    encoded.resize(2 + padding, 0xff);
+    encoded.push(0x00);
+    encoded.extend_from_slice(MD5_DIGEST_INFO);
+    encoded.extend_from_slice(&digest);
+    encoded
+}
+
+fn parse_public_key(der: &[u8]) -> Option<RsaPublicKey> {
+    let (tag, seq, _) = der_element(der)?;
+    if tag != 0x30 {
+        return None;
+    }
+    let (tag, first, rest) = der_element(seq)?;
+    match tag {
+        // PKCS#1: modulus, exponent
+        0x02 => {
+            let (tag, e, _) = der_element(rest)?;
+            if tag != 0x02 {
+                return None;
+            }
+            Some(RsaPublicKey {
+                n: BigUint::from_bytes_be(first),
+                e: BigUint::from_bytes_be(e),
+            })
+        }
+        // SubjectPublicKeyInfo: algorithm, then PKCS#1 in a bit string
+        0x30 => {
+            let (tag, bits, _) = der_element(rest)?;
+            if tag != 0x03 || bits.first() != Some(&0) {
+                return None;
+            }
+            parse_public_key(&bits[1..])
+        }
+        _ => None,
+    }
+}
+
+/// Splits a DER element into its tag, contents and the bytes after it.
+fn der_element(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, der) = der.split_first()?;
+    let (&len, mut der) = der.split_first()?;
+    let len = if len & 0x80 == 0 {
+        len as usize
+    } else {
+        let count = (len & 0x7f) as usize;
+        if count == 0 || count > 4 || der.len() < count {
+            return None;
+        }
+        let len = der[..count]
+            .iter()
+            .fold(0usize, |len, byte| len << 8 | *byte as usize);
+        der = &der[count..];
+        len
+    };
+    if der.len() < len {
+        return None;
+    }
+    Some((tag, &der[..len], &der[len..]))
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => escaped += "\\\"",
+            '\\' => escaped += "\\\\",
+            '\n' => escaped += "\\n",
+            '\r' => escaped += "\\r",
+            '\t' => escaped += "\\t",
+            c if (c as u32) < 0x20 => escaped += &format!("\\u{:04x}", c as u32),
+            c => escaped.push(c),
+        }
+    }
+    escaped + "\""
+}
+
+fn callback_error(msg: &str) -> Error {
+    Error::Object(ObjectError::CallbackError {
+        msg: msg.to_owned(),
+    })
+}
@@ -0,0 +1,260 @@
+//! Cassette-style recording and replaying of OSS traffic.
+//!
+//! `Recorder` forwards requests to the network and appends every
+//! request/response pair to a file, with signatures and credentials
+//! redacted. `Replayer` serves those pairs back in order without touching
+//! the network, so parsing can be regression-tested against real payloads:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use oss_rust_sdk::cassette::Replayer;
+//! use oss_rust_sdk::prelude::*;
+//!
+//! let mut oss_instance = OSS::new("id", "secret", "oss-cn-hangzhou.aliyuncs.com", "bucket");
+//! oss_instance.set_transport(Arc::new(Replayer::load("tests/cassettes/list.cassette").unwrap()));
+//! let objects = oss_instance.list_objects(None).unwrap();
+//! ```
+
+use base64::{decode, encode};
+use reqwest::blocking::{Client, Request};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::StatusCode;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Cursor, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::errors::Error;
+use super::transport::{Response, Transport};
+
+const REDACTED: &str = "<redacted>";
+const REDACTED_PARAMS: &[&str] = &["OSSAccessKeyId", "Signature", "security-token"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: Vec<u8>,
+    pub status: u16,
+    pub response_headers: Vec<(String, String)>,
+    pub response_body: Vec<u8>,
+}
+
+impl Interaction {
+    fn to_text(&self) -> String {
+        let mut text = format!("-> {} {}\n", self.method, self.url);
+        for (name, val) in self.request_headers.iter() {
+            text += &format!("-> {}: {}\n", name, val);
+        }
+        text += &format!("-> body {}\n", encode(&self.request_body));
+        text += &format!("<- {}\n", self.status);
+        for (name, val) in self.response_headers.iter() {
+            text += &format!("<- {}: {}\n", name, val);
+        }
+        text += &format!("<- body {}\n\n", encode(&self.response_body));
+        text
+    }
+
+    fn response(&self) -> Result<Response, Error> {
+        let status = StatusCode::from_u16(self.status)
+            .map_err(|_| cassette_error(format!("invalid status {}", self.status)))?;
+        let mut headers = HeaderMap::new();
+        for (name, val) in self.response_headers.iter() {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(val)?,
+            );
+        }
+        Ok(Response::new(
+            status,
+            headers,
+            Cursor::new(self.response_body.clone()),
+        ))
+    }
+}
+
+/// Parses the text written by `Recorder`.
+pub fn parse_cassette(text: &str) -> Result<Vec<Interaction>, Error> {
+    let mut interactions = vec![];
+    let mut cur: Option<Interaction> = None;
+    for (no, line) in text.lines().enumerate() {
+        let invalid = || cassette_error(format!("invalid cassette line {}: {}", no + 1, line));
+        if line.is_empty() {
+            interactions.extend(cur.take());
+            continue;
+        }
+        let (dir, rest) = line.split_at(line.len().min(3));
+        let it = cur.get_or_insert_with(Interaction::default);
+        match dir {
+            "-> " if it.method.is_empty() => {
+                let (method, url) = rest.split_once(' ').ok_or_else(invalid)?;
+                it.method = method.to_owned();
+                it.url = url.to_owned();
+            }
+            "<- " if it.status == 0 => it.status = rest.parse().map_err(|_| invalid())?,
+            "-> " | "<- " => {
+                let (body, headers) = if dir == "-> " {
+                    (&mut it.request_body, &mut it.request_headers)
+                } else {
+                    (&mut it.response_body, &mut it.response_headers)
+                };
+                let data = if rest == "body" {
+                    Some("")
+                } else {
+                    rest.strip_prefix("body ")
+                };
+                if let Some(data) = data {
+                    *body = decode(data).map_err(|_| invalid())?;
+                } else {
+                    let (name, val) = rest.split_once(": ").ok_or_else(invalid)?;
+                    headers.push((name.to_owned(), val.to_owned()));
+                }
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    interactions.extend(cur);
+    Ok(interactions)
+}
+
+fn cassette_error(msg: String) -> Error {
+    Error::Transport(msg)
+}
+
+fn redact_url(url: &str) -> String {
+    let (base, query) = match url.split_once('?') {
+        Some(parts) => parts,
+        None => return url.to_owned(),
+    };
+    let query: Vec<String> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((k, _)) if REDACTED_PARAMS.contains(&k) => format!("{}={}", k, REDACTED),
+            _ => pair.to_owned(),
+        })
+        .collect();
+    format!("{}?{}", base, query.join("&"))
+}
+
+fn redact_header(name: &str, val: &str) -> String {
+    match name {
+        "authorization" => format!("OSS {}", REDACTED),
+        "x-oss-security-token" => REDACTED.to_owned(),
+        _ => val.to_owned(),
+    }
+}
+
+fn capture_request(req: &Request) -> Interaction {
+    Interaction {
+        method: req.method().to_string(),
+        url: redact_url(req.url().as_str()),
+        request_headers: req
+            .headers()
+            .iter()
+            .map(|(name, val)| {
+                let val = String::from_utf8_lossy(val.as_bytes());
+                (name.to_string(), redact_header(name.as_str(), &val))
+            })
+            .collect(),
+        request_body: req
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(<[u8]>::to_vec)
+            .unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+/// Sends requests for real and appends each exchange to a cassette file.
+#[derive(Debug)]
+pub struct Recorder {
+    file: Mutex<File>,
+}
+
+impl Recorder {
+    /// Starts a new cassette at `path`, replacing an existing one.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(Recorder {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl Transport for Recorder {
+    fn send(&self, client: &Client, req: Request) -> Result<Response, Error> {
+        let mut interaction = capture_request(&req);
+        let resp = client.execute(req)?;
+        interaction.status = resp.status().as_u16();
+        interaction.response_headers = resp
+            .headers()
+            .iter()
+            .map(|(name, val)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(val.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        interaction.response_body = resp.bytes()?.to_vec();
+
+        let mut file = self.file.lock().unwrap();
+        file.write_all(interaction.to_text().as_bytes())?;
+        file.flush()?;
+        interaction.response()
+    }
+}
+
+/// Serves recorded exchanges back in order. Each request must have the
+/// method and (redacted) url of the next recorded one.
+#[derive(Debug)]
+pub struct Replayer {
+    interactions: Mutex<VecDeque<Interaction>>,
+}
+
+impl Replayer {
+    pub fn new(interactions: Vec<Interaction>) -> Self {
+        Replayer {
+            interactions: Mutex::new(interactions.into()),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(Self::new(parse_cassette(&fs::read_to_string(path)?)?))
+    }
+
+    /// Number of recorded exchanges not yet replayed.
+    pub fn remaining(&self) -> usize {
+        self.interactions.lock().unwrap().len()
+    }
+}
+
+impl Transport for Replayer {
+    fn send(&self, _client: &Client, req: Request) -> Result<Response, Error> {
+        let actual = capture_request(&req);
+        let expected = self
+            .interactions
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| {
+                cassette_error(format!(
+                    "no recorded response left for {} {}",
+                    actual.method, actual.url
+                ))
+            })?;
+        if expected.method != actual.method || expected.url != actual.url {
+            return Err(cassette_error(format!(
+                "expected {} {}, got {} {}",
+                expected.method, expected.url, actual.method, actual.url
+            )));
+        }
+        expected.response()
+    }
+}
@@ -0,0 +1,116 @@
+//! Clients scoped to a bucket.
+//!
+//! `OssClient` is owned, cheap to clone and `Send + Sync + 'static`; its
+//! clones share one connection pool. `bucket` hands out `BucketClient`s,
+//! which offer the whole object API for one bucket:
+//!
+//! ```no_run
+//! use oss_rust_sdk::prelude::*;
+//!
+//! let client = OssClient::new("your_AccessKeyId", "your_AccessKeySecret", "your_Endpoint");
+//! let logs = client.bucket("logs");
+//! let handle = std::thread::spawn(move || logs.put(b"line", "app.log", None));
+//! handle.join().unwrap().unwrap();
+//! ```
+//!
+//! `OSS::with_bucket` returns a borrowed `BucketHandle` instead, which copies
+//! nothing and leaves the instance untouched:
+//!
+//! ```no_run
+//! use oss_rust_sdk::prelude::*;
+//!
+//! let oss_instance = OSS::new("your_AccessKeyId", "your_AccessKeySecret", "your_Endpoint", "source");
+//! let backup = oss_instance.with_bucket("backup");
+//! let opts = CopyOptions {
+//!     source_bucket: Some(oss_instance.bucket().to_string()),
+//!     ..Default::default()
+//! };
+//! backup.copy_object("object", "object", &opts).unwrap();
+//! ```
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use super::oss::OSS;
+
+#[derive(Clone, Debug)]
+pub struct OssClient {
+    oss: Arc<OSS<'static>>,
+}
+
+impl OssClient {
+    pub fn new<S1, S2, S3>(key_id: S1, key_secret: S2, endpoint: S3) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+    {
+        OSS::new(
+            key_id.into(),
+            key_secret.into(),
+            endpoint.into(),
+            String::new(),
+        )
+        .into()
+    }
+
+    /// Object API scoped to `bucket`.
+    pub fn bucket<S>(&self, bucket: S) -> BucketClient
+    where
+        S: Into<String>,
+    {
+        let mut oss = OSS::clone(&self.oss);
+        oss.set_bucket(bucket.into());
+        BucketClient { oss: Arc::new(oss) }
+    }
+
+    /// The underlying instance, e.g. for `ServiceAPI`.
+    pub fn oss(&self) -> &OSS<'static> {
+        &self.oss
+    }
+}
+
+impl<'a> From<OSS<'a>> for OssClient {
+    /// Keeps the instance's settings, its transport and clock included.
+    fn from(oss: OSS<'a>) -> Self {
+        OssClient {
+            oss: Arc::new(oss.into_owned()),
+        }
+    }
+}
+
+/// A client bound to one bucket. Derefs to `OSS`, so every API trait
+/// implemented for `OSS` is available on it.
+#[derive(Clone, Debug)]
+pub struct BucketClient {
+    oss: Arc<OSS<'static>>,
+}
+
+impl Deref for BucketClient {
+    type Target = OSS<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.oss
+    }
+}
+
+/// A borrowed view of an `OSS` instance on another bucket, see
+/// `OSS::with_bucket`.
+#[derive(Clone, Debug)]
+pub struct BucketHandle<'a> {
+    oss: OSS<'a>,
+}
+
+impl<'a> BucketHandle<'a> {
+    pub(crate) fn new(oss: OSS<'a>) -> Self {
+        BucketHandle { oss }
+    }
+}
+
+impl<'a> Deref for BucketHandle<'a> {
+    type Target = OSS<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.oss
+    }
+}
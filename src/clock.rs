@@ -0,0 +1,70 @@
+//! Time sources used for request dates and presigned url expiries.
+
+use chrono::{DateTime, Duration, Utc};
+use std::fmt;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+pub trait Clock: Send + Sync + fmt::Debug {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Called with the server's time after a `RequestTimeTooSkewed` error.
+    /// Returns whether the clock adjusted itself, in which case the request
+    /// is signed and sent once more.
+    fn correct(&self, _server_time: DateTime<Utc>) -> bool {
+        false
+    }
+}
+
+/// The local system time, the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Always returns the same instant, for reproducible signatures.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// Wraps another clock and shifts it by its offset to the server's clock,
+/// learnt from the `Date` header of `RequestTimeTooSkewed` responses.
+#[derive(Debug, Default)]
+pub struct SkewCorrectedClock<C = SystemClock> {
+    inner: C,
+    offset_millis: AtomicI64,
+}
+
+impl<C: Clock> SkewCorrectedClock<C> {
+    pub fn new(inner: C) -> Self {
+        SkewCorrectedClock {
+            inner,
+            offset_millis: AtomicI64::new(0),
+        }
+    }
+
+    /// Current correction applied on top of the wrapped clock.
+    pub fn offset(&self) -> Duration {
+        Duration::milliseconds(self.offset_millis.load(Ordering::Relaxed))
+    }
+}
+
+impl<C: Clock> Clock for SkewCorrectedClock<C> {
+    fn now(&self) -> DateTime<Utc> {
+        self.inner.now() + self.offset()
+    }
+
+    fn correct(&self, server_time: DateTime<Utc>) -> bool {
+        let offset = (server_time - self.inner.now()).num_milliseconds();
+        self.offset_millis.store(offset, Ordering::Relaxed);
+        true
+    }
+}
@@ -0,0 +1,152 @@
+//! CRC-64/ECMA-182 as used by OSS in `x-oss-hash-crc64ecma`.
+
+use std::io::{self, Write};
+
+const POLY: u64 = 0xC96C_5795_D787_0F42;
+
+const TABLE: [u64; 256] = make_table();
+
+const fn make_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u64;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ POLY
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crc64 {
+    crc: u64,
+}
+
+impl Crc64 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let mut crc = !self.crc;
+        for byte in data {
+            crc = TABLE[((crc ^ u64::from(*byte)) & 0xff) as usize] ^ (crc >> 8);
+        }
+        self.crc = !crc;
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.crc
+    }
+}
+
+pub fn checksum(data: &[u8]) -> u64 {
+    let mut crc = Crc64::new();
+    crc.update(data);
+    crc.finish()
+}
+
+/// CRC64 of the concatenation `a ++ b`, given the checksums of both parts
+/// and the length of `b`, as needed to check a multipart upload.
+pub fn combine(crc_a: u64, crc_b: u64, len_b: u64) -> u64 {
+    if len_b == 0 {
+        return crc_a;
+    }
+    // operator for one zero bit, then squared to two and four bits
+    let mut odd = [0u64; 64];
+    odd[0] = POLY;
+    for (n, row) in odd.iter_mut().enumerate().skip(1) {
+        *row = 1 << (n - 1);
+    }
+    let mut even = gf2_matrix_square(&odd);
+    odd = gf2_matrix_square(&even);
+
+    // apply len_b zero bytes to crc_a, one bit of the length at a time
+    let mut crc = crc_a;
+    let mut len = len_b;
+    loop {
+        even = gf2_matrix_square(&odd);
+        if len & 1 == 1 {
+            crc = gf2_matrix_times(&even, crc);
+        }
+        len >>= 1;
+        if len == 0 {
+            break;
+        }
+        odd = gf2_matrix_square(&even);
+        if len & 1 == 1 {
+            crc = gf2_matrix_times(&odd, crc);
+        }
+        len >>= 1;
+        if len == 0 {
+            break;
+        }
+    }
+    crc ^ crc_b
+}
+
+fn gf2_matrix_times(mat: &[u64; 64], mut vec: u64) -> u64 {
+    let mut sum = 0;
+    let mut row = 0;
+    while vec != 0 {
+        if vec & 1 == 1 {
+            sum ^= mat[row];
+        }
+        vec >>= 1;
+        row += 1;
+    }
+    sum
+}
+
+fn gf2_matrix_square(mat: &[u64; 64]) -> [u64; 64] {
+    let mut square = [0u64; 64];
+    for (n, row) in square.iter_mut().enumerate() {
+        *row = gf2_matrix_times(mat, mat[n]);
+    }
+    square
+}
+
+/// Passes writes through to `inner` while checksumming them.
+#[derive(Debug)]
+pub struct Crc64Writer<W> {
+    inner: W,
+    crc: Crc64,
+}
+
+impl<W: Write> Crc64Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Crc64Writer {
+            inner,
+            crc: Crc64::new(),
+        }
+    }
+
+    pub fn crc64(&self) -> u64 {
+        self.crc.finish()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for Crc64Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.crc.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
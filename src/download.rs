@@ -0,0 +1,305 @@
+use std::collections::BTreeSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use super::crc64::Crc64;
+use super::errors::{Error, IntegrityError, ObjectError};
+use super::object::*;
+use super::oss::OSS;
+
+const DEFAULT_PART_SIZE: u64 = 8 * 1024 * 1024;
+const DEFAULT_PARALLEL: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Size of each ranged request.
+    pub part_size: u64,
+    /// Number of parts fetched at the same time.
+    pub parallel: usize,
+    /// Keep a `<path>.dcp` checkpoint so an interrupted download resumes.
+    pub checkpoint: bool,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            part_size: DEFAULT_PART_SIZE,
+            parallel: DEFAULT_PARALLEL,
+            checkpoint: true,
+        }
+    }
+}
+
+/// Progress of a download, persisted after every finished part.
+#[derive(Debug, Clone, PartialEq)]
+struct Checkpoint {
+    object: String,
+    e_tag: String,
+    size: u64,
+    part_size: u64,
+    done: BTreeSet<u64>,
+}
+
+impl Checkpoint {
+    fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let mut cp = Checkpoint {
+            object: String::new(),
+            e_tag: String::new(),
+            size: 0,
+            part_size: 0,
+            done: BTreeSet::new(),
+        };
+        for line in content.lines() {
+            let (key, val) = line.split_once('=')?;
+            match key {
+                "object" => cp.object = val.to_owned(),
+                "etag" => cp.e_tag = val.to_owned(),
+                "size" => cp.size = val.parse().ok()?,
+                "part_size" => cp.part_size = val.parse().ok()?,
+                "done" => {
+                    for part in val.split(',').filter(|p| !p.is_empty()) {
+                        cp.done.insert(part.parse().ok()?);
+                    }
+                }
+                _ => return None,
+            }
+        }
+        Some(cp)
+    }
+
+    fn store(&self, path: &Path) -> Result<(), Error> {
+        let done: Vec<String> = self.done.iter().map(|part| part.to_string()).collect();
+        let content = format!(
+            "object={}\netag={}\nsize={}\npart_size={}\ndone={}\n",
+            self.object,
+            self.e_tag,
+            self.size,
+            self.part_size,
+            done.join(",")
+        );
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    fn part_count(&self) -> u64 {
+        self.size.div_ceil(self.part_size)
+    }
+
+    fn part_range(&self, part: u64) -> (u64, u64) {
+        let start = part * self.part_size;
+        (start, (start + self.part_size).min(self.size) - 1)
+    }
+}
+
+pub trait DownloadAPI {
+    /// Downloads `object_name` into `path` with parallel ranged requests.
+    ///
+    /// Data goes to `<path>.tmp` first and is renamed once every part is
+    /// written and the CRC64 matches. With `opts.checkpoint` a failed call
+    /// can simply be repeated and only fetches the missing parts, as long as
+    /// the object did not change in between.
+    fn download_file<S, P>(
+        &self,
+        object_name: S,
+        path: P,
+        opts: &DownloadOptions,
+    ) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        P: AsRef<Path>;
+}
+
+impl<'a> DownloadAPI for OSS<'a> {
+    fn download_file<S, P>(
+        &self,
+        object_name: S,
+        path: P,
+        opts: &DownloadOptions,
+    ) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        let object_name = object_name.as_ref();
+        let path = path.as_ref();
+        let temp_path = with_suffix(path, ".tmp");
+        let cp_path = with_suffix(path, ".dcp");
+
+        let meta = self.head_object(object_name)?;
+        let fresh = Checkpoint {
+            object: object_name.to_owned(),
+            e_tag: meta.e_tag.clone(),
+            size: meta.content_length,
+            part_size: opts.part_size.max(1),
+            done: BTreeSet::new(),
+        };
+        let resumed = if opts.checkpoint {
+            Checkpoint::load(&cp_path).filter(|cp| {
+                cp.object == fresh.object
+                    && cp.e_tag == fresh.e_tag
+                    && cp.size == fresh.size
+                    && cp.part_size == fresh.part_size
+                    && fs::metadata(&temp_path).map(|m| m.len()).ok() == Some(fresh.size)
+            })
+        } else {
+            None
+        };
+        let checkpoint = match resumed {
+            Some(cp) => cp,
+            None => {
+                File::create(&temp_path)?.set_len(fresh.size)?;
+                fresh
+            }
+        };
+
+        let todo: Vec<u64> = (0..checkpoint.part_count())
+            .filter(|part| !checkpoint.done.contains(part))
+            .collect();
+        let resumed_bytes: u64 = checkpoint
+            .done
+            .iter()
+            .map(|&part| {
+                let (start, end) = checkpoint.part_range(part);
+                end - start + 1
+            })
+            .sum();
+        self.report_progress(object_name, resumed_bytes, Some(checkpoint.size));
+        let transferred = AtomicU64::new(resumed_bytes);
+        let quiet = self.without_progress();
+        let todo = Mutex::new(todo.into_iter());
+        let checkpoint = Mutex::new(checkpoint);
+        let failed = AtomicBool::new(false);
+
+        let results: Vec<Result<(), Error>> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..opts.parallel.max(1))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let work = || -> Result<(), Error> {
+                            let mut file = OpenOptions::new().write(true).open(&temp_path)?;
+                            loop {
+                                if failed.load(Ordering::SeqCst) {
+                                    return Ok(());
+                                }
+                                let part = match todo.lock().unwrap().next() {
+                                    Some(part) => part,
+                                    None => return Ok(()),
+                                };
+                                let (start, end) = checkpoint.lock().unwrap().part_range(part);
+                                let content =
+                                    quiet.download_range(object_name, &meta.e_tag, start, end)?;
+                                file.seek(SeekFrom::Start(start))?;
+                                file.write_all(&content)?;
+
+                                let mut cp = checkpoint.lock().unwrap();
+                                cp.done.insert(part);
+                                if opts.checkpoint {
+                                    cp.store(&cp_path)?;
+                                }
+                                let size = end - start + 1;
+                                let done = transferred.fetch_add(size, Ordering::SeqCst) + size;
+                                if let Some(listener) = self.progress_listener() {
+                                    listener.on_part(object_name, part as u32 + 1, size);
+                                    listener.on_progress(object_name, done, Some(cp.size));
+                                }
+                            }
+                        };
+                        // stop the other workers whatever went wrong here
+                        let ret = work();
+                        if ret.is_err() {
+                            failed.store(true, Ordering::SeqCst);
+                        }
+                        ret
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("download worker panicked"))
+                .collect()
+        });
+        if let Some(e) = results.into_iter().find_map(Result::err) {
+            // without a checkpoint the partial file can't be resumed
+            if !opts.checkpoint {
+                let _ = fs::remove_file(&temp_path);
+            }
+            return Err(e);
+        }
+
+        if let (Some(expected), true) = (meta.crc64, self.crc_check()) {
+            let actual = file_crc64(&temp_path)?;
+            if actual != expected {
+                let _ = fs::remove_file(&cp_path);
+                let _ = fs::remove_file(&temp_path);
+                return Err(Error::Integrity(IntegrityError {
+                    object: object_name.to_owned(),
+                    expected,
+                    actual,
+                }));
+            }
+        }
+        fs::rename(&temp_path, path)?;
+        if opts.checkpoint {
+            let _ = fs::remove_file(&cp_path);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> OSS<'a> {
+    /// Fetches `start..=end`, failing if the object no longer has `e_tag`.
+    fn download_range(
+        &self,
+        object_name: &str,
+        e_tag: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<u8>, Error> {
+        let opts = GetOptions {
+            range: Some(ByteRange::FromTo(start, end)),
+            if_match: Some(e_tag.to_owned()),
+            ..Default::default()
+        };
+        let content = match self.get_with_options(object_name, NULL_META, &opts)? {
+            ConditionalGetResponse::Partial { response, .. } => response.content,
+            ConditionalGetResponse::Full(response) => response.content,
+            ConditionalGetResponse::NotModified { .. } => vec![],
+        };
+        if content.len() as u64 != end - start + 1 {
+            return Err(Error::Object(ObjectError::DownloadError {
+                msg: format!(
+                    "short read for bytes {}-{} of {}, got {} bytes",
+                    start,
+                    end,
+                    object_name,
+                    content.len()
+                ),
+            }));
+        }
+        Ok(content)
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn file_crc64(path: &Path) -> Result<u64, Error> {
+    let mut file = File::open(path)?;
+    let mut crc = Crc64::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        crc.update(&buf[..n]);
+    }
+    Ok(crc.finish())
+}
@@ -0,0 +1,389 @@
+//! Client-side envelope encryption.
+//!
+//! `EncryptedClient` encrypts every object with AES-256-CTR under a fresh
+//! data key and IV. Both are wrapped by a `KeyProvider` holding the master
+//! key and stored base64-encoded in the `client-side-encryption-key` and
+//! `client-side-encryption-start` meta, as the official SDKs do, so OSS
+//! never sees plaintext or usable keys. CTR keeps ciphertext and plaintext
+//! offsets aligned, which makes ranged gets work unchanged.
+//!
+//! `AesKeyProvider` wraps data keys with a symmetric master key,
+//! `RsaKeyProvider` with an RSA key pair. Other master keys, e.g. in a KMS,
+//! only need their own `KeyProvider` implementation.
+
+use base64::{decode, encode};
+use crypto::aead::{AeadDecryptor, AeadEncryptor};
+use crypto::aes::{self, KeySize};
+use crypto::aes_gcm::AesGcm;
+use rand::{OsRng, Rng};
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::{Oaep, Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+use sha1::Sha1;
+use std::collections::HashMap;
+
+use super::errors::{Error, ObjectError};
+use super::object::{ConditionalGetResponse, GetBufferedObjResponse, GetOptions, ObjectAPI};
+use super::object::{PutObjectResponse, PutOptions};
+use super::oss::OSS;
+use super::utils::{to_meta_headers, OSS_META_PREFIX};
+
+const CSE_KEY: &str = "client-side-encryption-key";
+const CSE_START: &str = "client-side-encryption-start";
+const CSE_CEK_ALG: &str = "client-side-encryption-cek-alg";
+const CSE_WRAP_ALG: &str = "client-side-encryption-wrap-alg";
+const CSE_MATDESC: &str = "client-side-encryption-matdesc";
+const CSE_UNENCRYPTED_LENGTH: &str = "client-side-encryption-unencrypted-content-length";
+
+const CEK_ALG: &str = "AES/CTR/NoPadding";
+const DATA_KEY_LEN: usize = 32;
+const IV_LEN: usize = 16;
+const BLOCK_LEN: u64 = 16;
+
+/// Holds the master key that wraps per-object data keys and IVs.
+pub trait KeyProvider: Send + Sync {
+    fn wrap_key(&self, data_key: &[u8]) -> Result<Vec<u8>, Error>;
+    fn unwrap_key(&self, wrapped_key: &[u8]) -> Result<Vec<u8>, Error>;
+    /// Name of the wrapping algorithm, stored with each object.
+    fn algorithm(&self) -> &str;
+    /// Describes the master key, stored with each object so the right key
+    /// can be picked for decryption.
+    fn description(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Wraps data keys with AES-GCM under a 128, 192 or 256 bit master key.
+#[derive(Clone)]
+pub struct AesKeyProvider {
+    master_key: Vec<u8>,
+    key_size: KeySize,
+    description: Option<String>,
+}
+
+const GCM_NONCE_LEN: usize = 12;
+const GCM_TAG_LEN: usize = 16;
+
+impl AesKeyProvider {
+    pub fn new(master_key: &[u8]) -> Result<Self, Error> {
+        let key_size = match master_key.len() {
+            16 => KeySize::KeySize128,
+            24 => KeySize::KeySize192,
+            32 => KeySize::KeySize256,
+            len => {
+                return Err(encryption_error(format!(
+                    "master key must be 16, 24 or 32 bytes, got {}",
+                    len
+                )))
+            }
+        };
+        Ok(AesKeyProvider {
+            master_key: master_key.to_vec(),
+            key_size,
+            description: None,
+        })
+    }
+
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+impl KeyProvider for AesKeyProvider {
+    /// Returns nonce, ciphertext and tag, concatenated.
+    fn wrap_key(&self, data_key: &[u8]) -> Result<Vec<u8>, Error> {
+        let nonce = random_bytes(GCM_NONCE_LEN)?;
+        let mut wrapped = vec![0; GCM_NONCE_LEN + data_key.len() + GCM_TAG_LEN];
+        wrapped[..GCM_NONCE_LEN].copy_from_slice(&nonce);
+        let (body, tag) = wrapped[GCM_NONCE_LEN..].split_at_mut(data_key.len());
+        AesGcm::new(self.key_size, &self.master_key, &nonce, &[]).encrypt(data_key, body, tag);
+        Ok(wrapped)
+    }
+
+    fn unwrap_key(&self, wrapped_key: &[u8]) -> Result<Vec<u8>, Error> {
+        if wrapped_key.len() < GCM_NONCE_LEN + GCM_TAG_LEN {
+            return Err(encryption_error("wrapped data key is too short".to_owned()));
+        }
+        let (nonce, rest) = wrapped_key.split_at(GCM_NONCE_LEN);
+        let (body, tag) = rest.split_at(rest.len() - GCM_TAG_LEN);
+        let mut data_key = vec![0; body.len()];
+        if AesGcm::new(self.key_size, &self.master_key, nonce, &[]).decrypt(
+            body,
+            &mut data_key,
+            tag,
+        ) {
+            Ok(data_key)
+        } else {
+            Err(encryption_error(
+                "can not unwrap data key, wrong master key?".to_owned(),
+            ))
+        }
+    }
+
+    fn algorithm(&self) -> &str {
+        "AES/GCM/NoPadding"
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+}
+
+/// Padding `RsaKeyProvider` wraps data keys with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RsaPadding {
+    /// RSAES-OAEP with SHA-1 and MGF1, the default.
+    Oaep,
+    /// RSAES-PKCS1-v1_5, for master keys shared with older clients.
+    Pkcs1,
+}
+
+/// Wraps data keys with an RSA master key pair. Made from the public key
+/// alone it can encrypt objects but not decrypt them.
+#[derive(Clone)]
+pub struct RsaKeyProvider {
+    public_key: RsaPublicKey,
+    private_key: Option<RsaPrivateKey>,
+    padding: RsaPadding,
+    description: Option<String>,
+}
+
+impl RsaKeyProvider {
+    /// Reads a `PRIVATE KEY` (PKCS#8) or `RSA PRIVATE KEY` (PKCS#1) PEM.
+    pub fn new(private_key_pem: &str) -> Result<Self, Error> {
+        let private_key = RsaPrivateKey::from_pkcs8_pem(private_key_pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(private_key_pem))
+            .map_err(|e| encryption_error(format!("invalid RSA private key: {}", e)))?;
+        Ok(RsaKeyProvider {
+            public_key: private_key.to_public_key(),
+            private_key: Some(private_key),
+            padding: RsaPadding::Oaep,
+            description: None,
+        })
+    }
+
+    /// Reads a `PUBLIC KEY` or `RSA PUBLIC KEY` PEM.
+    pub fn with_public_key(public_key_pem: &str) -> Result<Self, Error> {
+        let public_key = RsaPublicKey::from_public_key_pem(public_key_pem)
+            .or_else(|_| RsaPublicKey::from_pkcs1_pem(public_key_pem))
+            .map_err(|e| encryption_error(format!("invalid RSA public key: {}", e)))?;
+        Ok(RsaKeyProvider {
+            public_key,
+            private_key: None,
+            padding: RsaPadding::Oaep,
+            description: None,
+        })
+    }
+
+    pub fn with_padding(mut self, padding: RsaPadding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+impl KeyProvider for RsaKeyProvider {
+    fn wrap_key(&self, data_key: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut rng = rsa::rand_core::OsRng;
+        match self.padding {
+            RsaPadding::Oaep => self
+                .public_key
+                .encrypt(&mut rng, Oaep::new::<Sha1>(), data_key),
+            RsaPadding::Pkcs1 => self.public_key.encrypt(&mut rng, Pkcs1v15Encrypt, data_key),
+        }
+        .map_err(|e| encryption_error(format!("can not wrap data key: {}", e)))
+    }
+
+    fn unwrap_key(&self, wrapped_key: &[u8]) -> Result<Vec<u8>, Error> {
+        let private_key = self.private_key.as_ref().ok_or_else(|| {
+            encryption_error("can not unwrap data key without the RSA private key".to_owned())
+        })?;
+        // blinded, so the timing of the private key operation leaks nothing
+        let mut rng = rsa::rand_core::OsRng;
+        match self.padding {
+            RsaPadding::Oaep => {
+                private_key.decrypt_blinded(&mut rng, Oaep::new::<Sha1>(), wrapped_key)
+            }
+            RsaPadding::Pkcs1 => {
+                private_key.decrypt_blinded(&mut rng, Pkcs1v15Encrypt, wrapped_key)
+            }
+        }
+        .map_err(|_| encryption_error("can not unwrap data key, wrong master key?".to_owned()))
+    }
+
+    fn algorithm(&self) -> &str {
+        match self.padding {
+            RsaPadding::Oaep => "RSA/NONE/OAEPWithSHA-1AndMGF1Padding",
+            RsaPadding::Pkcs1 => "RSA/NONE/PKCS1Padding",
+        }
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+}
+
+/// Encrypts objects on `put` and decrypts them on `get`. Objects without
+/// client-side encryption meta are returned as stored.
+pub struct EncryptedClient<'a, K> {
+    oss: OSS<'a>,
+    key_provider: K,
+}
+
+impl<'a, K: KeyProvider> EncryptedClient<'a, K> {
+    pub fn new(oss: OSS<'a>, key_provider: K) -> Self {
+        EncryptedClient { oss, key_provider }
+    }
+
+    /// The wrapped instance, for requests that need no encryption.
+    pub fn oss(&self) -> &OSS<'a> {
+        &self.oss
+    }
+
+    pub fn put<'b, S, O>(
+        &self,
+        buf: &[u8],
+        object_name: S,
+        opts: O,
+    ) -> Result<PutObjectResponse, Error>
+    where
+        S: AsRef<str>,
+        O: Into<Option<&'b PutOptions<'b>>>,
+    {
+        let data_key = random_bytes(DATA_KEY_LEN)?;
+        let iv = random_bytes(IV_LEN)?;
+        let mut content = vec![0; buf.len()];
+        aes::ctr(KeySize::KeySize256, &data_key, &iv).process(buf, &mut content);
+
+        let wrapped_key = encode(&self.key_provider.wrap_key(&data_key)?);
+        let iv = encode(&self.key_provider.wrap_key(&iv)?);
+        let length = buf.len().to_string();
+        let mut meta = HashMap::new();
+        meta.insert(CSE_KEY, wrapped_key.as_str());
+        meta.insert(CSE_START, iv.as_str());
+        meta.insert(CSE_CEK_ALG, CEK_ALG);
+        meta.insert(CSE_WRAP_ALG, self.key_provider.algorithm());
+        meta.insert(CSE_UNENCRYPTED_LENGTH, length.as_str());
+        if let Some(description) = self.key_provider.description() {
+            meta.insert(CSE_MATDESC, description);
+        }
+
+        let mut opts = match opts.into() {
+            Some(_opts) => _opts.clone(),
+            None => PutOptions::default(),
+        };
+        opts.headers.extend(to_meta_headers(meta)?);
+        self.oss.put(&content, object_name, &opts)
+    }
+
+    pub fn get_as_buffer<S1, S2, M>(
+        &self,
+        object_name: S1,
+        meta_keys: M,
+    ) -> Result<GetBufferedObjResponse, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        M: Into<Vec<S2>>,
+    {
+        let mut resp = self.oss.get_as_buffer(object_name, meta_keys, None)?;
+        self.decrypt(&mut resp, 0)?;
+        Ok(resp)
+    }
+
+    /// Same as `ObjectAPI::get_with_options`, ranges refer to plaintext
+    /// offsets.
+    pub fn get_with_options<S1, S2, M>(
+        &self,
+        object_name: S1,
+        meta_keys: M,
+        opts: &GetOptions,
+    ) -> Result<ConditionalGetResponse, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        M: Into<Vec<S2>>,
+    {
+        let mut ret = self.oss.get_with_options(object_name, meta_keys, opts)?;
+        match &mut ret {
+            ConditionalGetResponse::Full(response) => self.decrypt(response, 0)?,
+            ConditionalGetResponse::Partial {
+                content_range,
+                response,
+            } => self.decrypt(response, content_range.start)?,
+            ConditionalGetResponse::NotModified { .. } => (),
+        }
+        Ok(ret)
+    }
+
+    /// Decrypts `resp.content` in place, which starts at `offset` of the
+    /// object.
+    fn decrypt(&self, resp: &mut GetBufferedObjResponse, offset: u64) -> Result<(), Error> {
+        let meta = |name: &str| {
+            resp.headers
+                .get(format!("{}{}", OSS_META_PREFIX, name).as_str())
+                .map(|val| val.to_str())
+                .transpose()
+        };
+        let wrapped_key = match meta(CSE_KEY)? {
+            Some(wrapped_key) => wrapped_key,
+            None => return Ok(()),
+        };
+        if let Some(alg) = meta(CSE_CEK_ALG)?.filter(|alg| *alg != CEK_ALG) {
+            return Err(encryption_error(format!(
+                "unsupported content encryption: {}",
+                alg
+            )));
+        }
+        let invalid = |name| encryption_error(format!("invalid {} meta", name));
+        let wrapped_key = decode(wrapped_key).map_err(|_| invalid(CSE_KEY))?;
+        let wrapped_iv = decode(meta(CSE_START)?.ok_or_else(|| invalid(CSE_START))?)
+            .map_err(|_| invalid(CSE_START))?;
+        let mut iv = self.key_provider.unwrap_key(&wrapped_iv)?;
+        if iv.len() != IV_LEN {
+            return Err(invalid(CSE_START));
+        }
+        let data_key = self.key_provider.unwrap_key(&wrapped_key)?;
+        if data_key.len() != DATA_KEY_LEN {
+            return Err(invalid(CSE_KEY));
+        }
+
+        // jump to the counter block containing `offset`, then skip into it
+        add_counter(&mut iv, offset / BLOCK_LEN);
+        let mut cipher = aes::ctr(KeySize::KeySize256, &data_key, &iv);
+        let skip = vec![0; (offset % BLOCK_LEN) as usize];
+        cipher.process(&skip, &mut skip.clone());
+        let mut plain = vec![0; resp.content.len()];
+        cipher.process(&resp.content, &mut plain);
+        resp.content = plain;
+        Ok(())
+    }
+}
+
+/// Adds `blocks` to the big-endian 128-bit counter `iv`.
+fn add_counter(iv: &mut [u8], blocks: u64) {
+    let mut carry = blocks as u128;
+    for byte in iv.iter_mut().rev() {
+        if carry == 0 {
+            break;
+        }
+        let sum = *byte as u128 + (carry & 0xff);
+        *byte = sum as u8;
+        carry = (carry >> 8) + (sum >> 8);
+    }
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![0; len];
+    OsRng::new()?.fill_bytes(&mut bytes);
+    Ok(bytes)
+}
+
+fn encryption_error(msg: String) -> Error {
+    Error::Object(ObjectError::EncryptionError { msg })
+}
@@ -4,6 +4,7 @@ use reqwest::header::InvalidHeaderValue as HttpInvalidHeaderValueError;
 use reqwest::header::ToStrError;
 use reqwest::Error as ReqwestError;
 use std::io::Error as IoError;
+use std::num::ParseIntError;
 use std::string::FromUtf8Error;
 use std::{error::Error as StdError, str::ParseBoolError};
 
@@ -11,12 +12,30 @@ use std::{error::Error as StdError, str::ParseBoolError};
 pub enum Error {
     Convert(ToStrError),
     Object(ObjectError),
+    Bucket(BucketError),
     Io(IoError),
     String(FromUtf8Error),
     Reqwest(ReqwestError),
     Qxml(QxmlError),
     Http(HttpError),
     ParseBool(ParseBoolError),
+    ParseInt(ParseIntError),
+    Transport(String),
+    Integrity(IntegrityError),
+}
+
+/// The data sent or received does not match the CRC64 reported by OSS.
+#[derive(Debug, Display)]
+#[display(
+    fmt = "crc64 mismatch for {}, expected {} got {}",
+    object,
+    expected,
+    actual
+)]
+pub struct IntegrityError {
+    pub object: String,
+    pub expected: u64,
+    pub actual: u64,
 }
 
 #[derive(Debug, Display)]
@@ -73,6 +92,12 @@ impl From<ParseBoolError> for Error {
     }
 }
 
+impl From<ParseIntError> for Error {
+    fn from(e: ParseIntError) -> Error {
+        Error::ParseInt(e)
+    }
+}
+
 #[derive(Debug, Display)]
 pub enum ObjectError {
     #[display(fmt = "PUT ERROR: {}", msg)]
@@ -85,6 +110,26 @@ pub enum ObjectError {
     DeleteError { msg: String },
     #[display(fmt = "HEAD ERROR: {}", msg)]
     HeadError { msg: String },
+    #[display(fmt = "RESTORE ERROR: {}", msg)]
+    RestoreError { msg: String },
+    #[display(fmt = "DOWNLOAD ERROR: {}", msg)]
+    DownloadError { msg: String },
+    #[display(fmt = "ENCRYPTION ERROR: {}", msg)]
+    EncryptionError { msg: String },
+    #[display(fmt = "SELECT ERROR: {}", msg)]
+    SelectError { msg: String },
+    #[display(fmt = "CALLBACK ERROR: {}", msg)]
+    CallbackError { msg: String },
+}
+
+#[derive(Debug, Display)]
+pub enum BucketError {
+    #[display(fmt = "GET BUCKET ERROR: {}", msg)]
+    GetError { msg: String },
+    #[display(fmt = "PUT BUCKET ERROR: {}", msg)]
+    PutError { msg: String },
+    #[display(fmt = "DELETE BUCKET ERROR: {}", msg)]
+    DeleteError { msg: String },
 }
 
 impl StdError for Error {}
@@ -0,0 +1,327 @@
+//! Image processing through `x-oss-process`.
+//!
+//! `ImageProcess` builds the `image/...` style string, which is passed to
+//! `ImageAPI::get_processed_image`, to presigned urls via `to_params`, or to
+//! `ImageAPI::process_and_save` to store the result as a new object.
+
+use base64::{encode_config, URL_SAFE_NO_PAD};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH};
+use reqwest::Method;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::errors::{Error, ObjectError};
+use super::object::{GetBufferedObjResponse, ObjectAPI};
+use super::oss::{SignedRequest, OSS};
+use super::utils::encode_query;
+
+const OSS_PROCESS: &str = "x-oss-process";
+
+/// How `resize` fits the image into the given box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeMode {
+    /// Largest image inside the box, keeping the aspect ratio.
+    Lfit,
+    /// Smallest image covering the box, keeping the aspect ratio.
+    Mfit,
+    /// Covers the box and crops the overflow from the center.
+    Fill,
+    /// Fits inside the box and pads the rest.
+    Pad,
+    /// Exactly the box, ignoring the aspect ratio.
+    Fixed,
+}
+
+impl fmt::Display for ResizeMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResizeMode::Lfit => write!(f, "lfit"),
+            ResizeMode::Mfit => write!(f, "mfit"),
+            ResizeMode::Fill => write!(f, "fill"),
+            ResizeMode::Pad => write!(f, "pad"),
+            ResizeMode::Fixed => write!(f, "fixed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Jpg,
+    Png,
+    Webp,
+    Bmp,
+    Gif,
+    Tiff,
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageFormat::Jpg => write!(f, "jpg"),
+            ImageFormat::Png => write!(f, "png"),
+            ImageFormat::Webp => write!(f, "webp"),
+            ImageFormat::Bmp => write!(f, "bmp"),
+            ImageFormat::Gif => write!(f, "gif"),
+            ImageFormat::Tiff => write!(f, "tiff"),
+        }
+    }
+}
+
+/// Anchor of crops and watermarks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gravity {
+    NorthWest,
+    North,
+    NorthEast,
+    West,
+    Center,
+    East,
+    SouthWest,
+    South,
+    SouthEast,
+}
+
+impl fmt::Display for Gravity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Gravity::NorthWest => write!(f, "nw"),
+            Gravity::North => write!(f, "north"),
+            Gravity::NorthEast => write!(f, "ne"),
+            Gravity::West => write!(f, "west"),
+            Gravity::Center => write!(f, "center"),
+            Gravity::East => write!(f, "east"),
+            Gravity::SouthWest => write!(f, "sw"),
+            Gravity::South => write!(f, "south"),
+            Gravity::SouthEast => write!(f, "se"),
+        }
+    }
+}
+
+/// A text or image watermark, see `ImageProcess::watermark`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watermark {
+    params: Vec<String>,
+}
+
+impl Watermark {
+    pub fn text<S: AsRef<str>>(text: S) -> Self {
+        Watermark {
+            params: vec![format!("text_{}", base64url(text.as_ref()))],
+        }
+    }
+
+    /// Uses the object `key`, in the same bucket, as watermark.
+    pub fn image<S: AsRef<str>>(key: S) -> Self {
+        Watermark {
+            params: vec![format!("image_{}", base64url(key.as_ref()))],
+        }
+    }
+
+    pub fn gravity(self, gravity: Gravity) -> Self {
+        self.param(format!("g_{}", gravity))
+    }
+
+    /// Distance from the edges of the anchor, in pixels.
+    pub fn offset(self, x: u32, y: u32) -> Self {
+        self.param(format!("x_{}", x)).param(format!("y_{}", y))
+    }
+
+    /// 0 is transparent, 100 opaque.
+    pub fn transparency(self, percent: u8) -> Self {
+        self.param(format!("t_{}", percent))
+    }
+
+    /// Text only, hex color such as `FF0000`.
+    pub fn color<S: AsRef<str>>(self, color: S) -> Self {
+        self.param(format!("color_{}", color.as_ref()))
+    }
+
+    /// Text only, font size in pixels.
+    pub fn size(self, size: u32) -> Self {
+        self.param(format!("size_{}", size))
+    }
+
+    fn param(mut self, param: String) -> Self {
+        self.params.push(param);
+        self
+    }
+}
+
+/// Operations applied in the order they were added, rendered as
+/// `image/resize,w_100/format,png`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageProcess {
+    operations: Vec<String>,
+}
+
+impl ImageProcess {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resizes to `width` and/or `height`, at least one should be set.
+    pub fn resize<W, H>(self, mode: ResizeMode, width: W, height: H) -> Self
+    where
+        W: Into<Option<u32>>,
+        H: Into<Option<u32>>,
+    {
+        let mut operation = format!("resize,m_{}", mode);
+        if let Some(width) = width.into() {
+            operation += &format!(",w_{}", width);
+        }
+        if let Some(height) = height.into() {
+            operation += &format!(",h_{}", height);
+        }
+        self.operation(operation)
+    }
+
+    /// Cuts `width` x `height` at `x`, `y` from the `gravity` anchor, the
+    /// top left corner by default.
+    pub fn crop<G>(self, x: u32, y: u32, width: u32, height: u32, gravity: G) -> Self
+    where
+        G: Into<Option<Gravity>>,
+    {
+        let mut operation = format!("crop,x_{},y_{},w_{},h_{}", x, y, width, height);
+        if let Some(gravity) = gravity.into() {
+            operation += &format!(",g_{}", gravity);
+        }
+        self.operation(operation)
+    }
+
+    /// Rotates clockwise by `degrees`, 0 to 360.
+    pub fn rotate(self, degrees: u32) -> Self {
+        self.operation(format!("rotate,{}", degrees))
+    }
+
+    pub fn format(self, format: ImageFormat) -> Self {
+        self.operation(format!("format,{}", format))
+    }
+
+    /// JPG and WebP quality, 1 to 100, relative to the original's.
+    pub fn quality(self, quality: u8) -> Self {
+        self.operation(format!("quality,q_{}", quality))
+    }
+
+    /// JPG and WebP quality, 1 to 100, absolute.
+    pub fn absolute_quality(self, quality: u8) -> Self {
+        self.operation(format!("quality,Q_{}", quality))
+    }
+
+    pub fn watermark(self, watermark: Watermark) -> Self {
+        self.operation(format!("watermark,{}", watermark.params.join(",")))
+    }
+
+    /// Query params for `signiture_url_with_params` and the `params` of
+    /// `ObjectAPI::get`.
+    pub fn to_params(&self) -> HashMap<String, Option<String>> {
+        let mut params = HashMap::new();
+        params.insert(OSS_PROCESS.to_owned(), Some(self.to_string()));
+        params
+    }
+
+    fn operation(mut self, operation: String) -> Self {
+        self.operations.push(operation);
+        self
+    }
+}
+
+impl fmt::Display for ImageProcess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "image")?;
+        for operation in &self.operations {
+            write!(f, "/{}", operation)?;
+        }
+        Ok(())
+    }
+}
+
+pub trait ImageAPI {
+    /// Gets the object with `process` applied.
+    fn get_processed_image<S>(
+        &self,
+        object_name: S,
+        process: &ImageProcess,
+    ) -> Result<GetBufferedObjResponse, Error>
+    where
+        S: AsRef<str>;
+    /// Applies `process` and stores the result as `target`, in
+    /// `target_bucket` or the current bucket.
+    fn process_and_save<'a, S1, S2, B>(
+        &self,
+        object_name: S1,
+        process: &ImageProcess,
+        target: S2,
+        target_bucket: B,
+    ) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        B: Into<Option<&'a str>>;
+}
+
+impl<'a> ImageAPI for OSS<'a> {
+    fn get_processed_image<S>(
+        &self,
+        object_name: S,
+        process: &ImageProcess,
+    ) -> Result<GetBufferedObjResponse, Error>
+    where
+        S: AsRef<str>,
+    {
+        self.get_as_buffer(
+            object_name.as_ref(),
+            Vec::<String>::new(),
+            process.to_params(),
+        )
+    }
+
+    fn process_and_save<'b, S1, S2, B>(
+        &self,
+        object_name: S1,
+        process: &ImageProcess,
+        target: S2,
+        target_bucket: B,
+    ) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        B: Into<Option<&'b str>>,
+    {
+        let mut body = format!(
+            "{}={}|sys/saveas,o_{}",
+            OSS_PROCESS,
+            process,
+            base64url(target.as_ref())
+        );
+        if let Some(bucket) = target_bucket.into() {
+            body += &format!(",b_{}", base64url(bucket));
+        }
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::POST,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: &encode_query(OSS_PROCESS),
+            resources: OSS_PROCESS,
+            headers,
+            body: Some(body.into_bytes()),
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Object(ObjectError::PutError {
+                msg: format!(
+                    "can not save processed image, status code: {}",
+                    resp.status()
+                ),
+            }))
+        }
+    }
+}
+
+fn base64url(s: &str) -> String {
+    encode_config(s, URL_SAFE_NO_PAD)
+}
@@ -3,11 +3,31 @@ extern crate derive_more;
 #[macro_use]
 extern crate log;
 
+pub mod bucket;
+pub mod callback;
+pub mod cassette;
+pub mod client;
+pub mod clock;
+pub mod crc64;
+pub mod download;
+pub mod encryption;
 pub mod errors;
+pub mod image;
+pub mod live;
+pub mod mime;
+pub mod multipart;
 pub mod object;
 pub mod oss;
 pub mod prelude;
+pub mod progress;
+pub mod select;
 pub mod service;
+pub mod sse;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod throttle;
+pub mod transport;
+pub mod website;
 
 mod auth;
 mod utils;
@@ -0,0 +1,754 @@
+//! LiveChannels: RTMP ingest into HLS playlists stored in the bucket.
+
+use quick_xml::{events::Event, Reader};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH};
+use reqwest::Method;
+use std::fmt;
+use std::str::FromStr;
+
+use super::auth::Auth;
+use super::errors::{BucketError, Error};
+use super::oss::{SignedRequest, OSS};
+use super::utils::{escape_xml, uri_encode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveChannelStatus {
+    Enabled,
+    /// Pushing is refused.
+    Disabled,
+}
+
+impl fmt::Display for LiveChannelStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LiveChannelStatus::Enabled => write!(f, "enabled"),
+            LiveChannelStatus::Disabled => write!(f, "disabled"),
+        }
+    }
+}
+
+impl FromStr for LiveChannelStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "enabled" => Ok(LiveChannelStatus::Enabled),
+            "disabled" => Ok(LiveChannelStatus::Disabled),
+            _ => Err(Error::Bucket(BucketError::GetError {
+                msg: format!("unknown live channel status: {}", s),
+            })),
+        }
+    }
+}
+
+/// HLS output of a channel, the only target type OSS supports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveChannelTarget {
+    /// Seconds per ts fragment.
+    pub frag_duration: u32,
+    /// Fragments listed in the playlist.
+    pub frag_count: u32,
+    /// Ends with `.m3u8`, stored as `{channel}/{playlist_name}`.
+    pub playlist_name: String,
+}
+
+impl Default for LiveChannelTarget {
+    fn default() -> Self {
+        LiveChannelTarget {
+            frag_duration: 5,
+            frag_count: 3,
+            playlist_name: "playlist.m3u8".to_owned(),
+        }
+    }
+}
+
+/// High-frequency snapshots, sent to an MNS topic.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiveChannelSnapshot {
+    pub role_name: String,
+    pub dest_bucket: String,
+    pub notify_topic: String,
+    /// Seconds between snapshots.
+    pub interval: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveChannelConfiguration {
+    pub description: String,
+    pub status: LiveChannelStatus,
+    pub target: LiveChannelTarget,
+    pub snapshot: Option<LiveChannelSnapshot>,
+}
+
+impl Default for LiveChannelConfiguration {
+    fn default() -> Self {
+        LiveChannelConfiguration {
+            description: String::new(),
+            status: LiveChannelStatus::Enabled,
+            target: LiveChannelTarget::default(),
+            snapshot: None,
+        }
+    }
+}
+
+impl LiveChannelConfiguration {
+    fn to_xml(&self) -> String {
+        let mut xml = format!(
+            "<LiveChannelConfiguration><Description>{}</Description><Status>{}</Status>",
+            escape_xml(&self.description),
+            self.status
+        );
+        xml += &format!(
+            "<Target><Type>HLS</Type><FragDuration>{}</FragDuration><FragCount>{}</FragCount><PlaylistName>{}</PlaylistName></Target>",
+            self.target.frag_duration,
+            self.target.frag_count,
+            escape_xml(&self.target.playlist_name)
+        );
+        if let Some(snapshot) = &self.snapshot {
+            xml += &format!(
+                "<Snapshot><RoleName>{}</RoleName><DestBucket>{}</DestBucket><NotifyTopic>{}</NotifyTopic><Interval>{}</Interval></Snapshot>",
+                escape_xml(&snapshot.role_name),
+                escape_xml(&snapshot.dest_bucket),
+                escape_xml(&snapshot.notify_topic),
+                snapshot.interval
+            );
+        }
+        xml + "</LiveChannelConfiguration>"
+    }
+}
+
+/// Where to push to and play from a new channel.
+#[derive(Debug, Clone, Default)]
+pub struct CreateLiveChannelResponse {
+    pub publish_urls: Vec<String>,
+    pub play_urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveChannel {
+    pub name: String,
+    pub description: String,
+    pub status: LiveChannelStatus,
+    pub last_modified: String,
+    pub publish_urls: Vec<String>,
+    pub play_urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ListLiveChannelsOptions {
+    pub prefix: String,
+    pub marker: String,
+    pub max_keys: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ListLiveChannelsResponse {
+    pub is_truncated: bool,
+    pub next_marker: String,
+    pub channels: Vec<LiveChannel>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiveChannelVideo {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: u32,
+    /// Bytes per second.
+    pub bandwidth: u64,
+    pub codec: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiveChannelAudio {
+    /// Bytes per second.
+    pub bandwidth: u64,
+    pub sample_rate: u32,
+    pub codec: String,
+}
+
+/// Push state of a channel. Only `Live` channels carry the connection and
+/// stream details.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiveChannelStat {
+    /// `Idle` or `Live`.
+    pub status: String,
+    pub connected_time: Option<String>,
+    pub remote_addr: Option<String>,
+    pub video: Option<LiveChannelVideo>,
+    pub audio: Option<LiveChannelAudio>,
+}
+
+/// One past push session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiveRecord {
+    pub start_time: String,
+    pub end_time: String,
+    pub remote_addr: String,
+}
+
+pub trait LiveChannelAPI {
+    fn create_live_channel<S>(
+        &self,
+        channel: S,
+        config: &LiveChannelConfiguration,
+    ) -> Result<CreateLiveChannelResponse, Error>
+    where
+        S: AsRef<str>;
+    fn get_live_channel_info<S>(&self, channel: S) -> Result<LiveChannelConfiguration, Error>
+    where
+        S: AsRef<str>;
+    fn put_live_channel_status<S>(
+        &self,
+        channel: S,
+        status: LiveChannelStatus,
+    ) -> Result<(), Error>
+    where
+        S: AsRef<str>;
+    fn list_live_channels<'a, O>(&self, opts: O) -> Result<ListLiveChannelsResponse, Error>
+    where
+        O: Into<Option<&'a ListLiveChannelsOptions>>;
+    fn delete_live_channel<S>(&self, channel: S) -> Result<(), Error>
+    where
+        S: AsRef<str>;
+    fn get_live_channel_stat<S>(&self, channel: S) -> Result<LiveChannelStat, Error>
+    where
+        S: AsRef<str>;
+    /// The last push sessions, at most 10.
+    fn get_live_channel_history<S>(&self, channel: S) -> Result<Vec<LiveRecord>, Error>
+    where
+        S: AsRef<str>;
+    /// Stores a VOD playlist of the fragments pushed between the unix
+    /// timestamps `start_time` and `end_time`, as `{channel}/{playlist}`.
+    fn post_vod_playlist<S1, S2>(
+        &self,
+        channel: S1,
+        playlist: S2,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>;
+    /// The m3u8 of the fragments pushed between `start_time` and
+    /// `end_time`, without storing it.
+    fn get_vod_playlist<S>(
+        &self,
+        channel: S,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<String, Error>
+    where
+        S: AsRef<str>;
+}
+
+impl<'a> LiveChannelAPI for OSS<'a> {
+    fn create_live_channel<S>(
+        &self,
+        channel: S,
+        config: &LiveChannelConfiguration,
+    ) -> Result<CreateLiveChannelResponse, Error>
+    where
+        S: AsRef<str>,
+    {
+        let body = config.to_xml();
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: channel.as_ref(),
+            params: "live",
+            resources: "live",
+            headers,
+            body: Some(body.into_bytes()),
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Bucket(BucketError::PutError {
+                msg: format!(
+                    "can not create live channel, status code: {}",
+                    resp.status()
+                ),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        let mut result = CreateLiveChannelResponse::default();
+        let mut in_publish = false;
+        reader.trim_text(true);
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match e.name() {
+                    b"PublishUrls" => in_publish = true,
+                    b"PlayUrls" => in_publish = false,
+                    b"Url" => {
+                        let url = reader.read_text(e.name(), &mut Vec::new())?;
+                        if in_publish {
+                            result.publish_urls.push(url);
+                        } else {
+                            result.play_urls.push(url);
+                        }
+                    }
+                    _ => (),
+                },
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(result)
+    }
+
+    fn get_live_channel_info<S>(&self, channel: S) -> Result<LiveChannelConfiguration, Error>
+    where
+        S: AsRef<str>,
+    {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: channel.as_ref(),
+            params: "live",
+            resources: "live",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Bucket(BucketError::GetError {
+                msg: format!("can not get live channel, status code: {}", resp.status()),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        let mut config = LiveChannelConfiguration::default();
+        let mut snapshot = LiveChannelSnapshot::default();
+        let mut has_snapshot = false;
+        reader.trim_text(true);
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match e.name() {
+                    b"Description" => {
+                        config.description = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    b"Status" => {
+                        config.status = reader.read_text(e.name(), &mut Vec::new())?.parse()?
+                    }
+                    b"FragDuration" => {
+                        config.target.frag_duration =
+                            parse_number(reader.read_text(e.name(), &mut Vec::new())?)?
+                    }
+                    b"FragCount" => {
+                        config.target.frag_count =
+                            parse_number(reader.read_text(e.name(), &mut Vec::new())?)?
+                    }
+                    b"PlaylistName" => {
+                        config.target.playlist_name = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    b"Snapshot" => has_snapshot = true,
+                    b"RoleName" => {
+                        snapshot.role_name = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    b"DestBucket" => {
+                        snapshot.dest_bucket = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    b"NotifyTopic" => {
+                        snapshot.notify_topic = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    b"Interval" => {
+                        snapshot.interval =
+                            parse_number(reader.read_text(e.name(), &mut Vec::new())?)?
+                    }
+                    _ => (),
+                },
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        if has_snapshot {
+            config.snapshot = Some(snapshot);
+        }
+        Ok(config)
+    }
+
+    fn put_live_channel_status<S>(&self, channel: S, status: LiveChannelStatus) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        let resources = format!("live&status={}", status);
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: channel.as_ref(),
+            params: &resources,
+            resources: &resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Bucket(BucketError::PutError {
+                msg: format!(
+                    "can not put live channel status, status code: {}",
+                    resp.status()
+                ),
+            }))
+        }
+    }
+
+    fn list_live_channels<'b, O>(&self, opts: O) -> Result<ListLiveChannelsResponse, Error>
+    where
+        O: Into<Option<&'b ListLiveChannelsOptions>>,
+    {
+        let params_string =
+            get_list_live_channels_params_str(opts.into().unwrap_or(&Default::default()));
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: "",
+            params: &params_string,
+            resources: "live",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Bucket(BucketError::GetError {
+                msg: format!("can not list live channels, status code: {}", resp.status()),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        let mut result = ListLiveChannelsResponse::default();
+        let mut cur = None;
+        let mut in_publish = false;
+        reader.trim_text(true);
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match (e.name(), cur.as_mut()) {
+                    (b"IsTruncated", None) => {
+                        result.is_truncated = reader.read_text(e.name(), &mut Vec::new())? == "true"
+                    }
+                    (b"NextMarker", None) => {
+                        result.next_marker = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    (b"LiveChannel", None) => {
+                        cur = Some(LiveChannel {
+                            name: String::new(),
+                            description: String::new(),
+                            status: LiveChannelStatus::Enabled,
+                            last_modified: String::new(),
+                            publish_urls: vec![],
+                            play_urls: vec![],
+                        })
+                    }
+                    (b"Name", Some(channel)) => {
+                        channel.name = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    (b"Description", Some(channel)) => {
+                        channel.description = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    (b"Status", Some(channel)) => {
+                        channel.status = reader.read_text(e.name(), &mut Vec::new())?.parse()?
+                    }
+                    (b"LastModified", Some(channel)) => {
+                        channel.last_modified = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    (b"PublishUrls", Some(_)) => in_publish = true,
+                    (b"PlayUrls", Some(_)) => in_publish = false,
+                    (b"Url", Some(channel)) => {
+                        let url = reader.read_text(e.name(), &mut Vec::new())?;
+                        if in_publish {
+                            channel.publish_urls.push(url);
+                        } else {
+                            channel.play_urls.push(url);
+                        }
+                    }
+                    _ => (),
+                },
+                Event::End(ref e) if e.name() == b"LiveChannel" => {
+                    result.channels.extend(cur.take());
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(result)
+    }
+
+    fn delete_live_channel<S>(&self, channel: S) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::DELETE,
+            bucket: self.bucket(),
+            object: channel.as_ref(),
+            params: "live",
+            resources: "live",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Bucket(BucketError::DeleteError {
+                msg: format!(
+                    "can not delete live channel, status code: {}",
+                    resp.status()
+                ),
+            }))
+        }
+    }
+
+    fn get_live_channel_stat<S>(&self, channel: S) -> Result<LiveChannelStat, Error>
+    where
+        S: AsRef<str>,
+    {
+        let resources = "comp=stat&live";
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: channel.as_ref(),
+            params: resources,
+            resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Bucket(BucketError::GetError {
+                msg: format!(
+                    "can not get live channel stat, status code: {}",
+                    resp.status()
+                ),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        let mut stat = LiveChannelStat::default();
+        let mut section = Vec::new();
+        reader.trim_text(true);
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match (section.as_slice(), e.name()) {
+                    (_, b"Video") | (_, b"Audio") => section = e.name().to_vec(),
+                    (b"", b"Status") => {
+                        stat.status = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    (b"", b"ConnectedTime") => {
+                        stat.connected_time = Some(reader.read_text(e.name(), &mut Vec::new())?)
+                    }
+                    (b"", b"RemoteAddr") => {
+                        stat.remote_addr = Some(reader.read_text(e.name(), &mut Vec::new())?)
+                    }
+                    (b"Video", name) => {
+                        let video = stat.video.get_or_insert_with(Default::default);
+                        let text = reader.read_text(name, &mut Vec::new())?;
+                        match name {
+                            b"Width" => video.width = parse_number(text)?,
+                            b"Height" => video.height = parse_number(text)?,
+                            b"FrameRate" => video.frame_rate = parse_number(text)?,
+                            b"Bandwidth" => video.bandwidth = parse_number(text)?,
+                            b"Codec" => video.codec = text,
+                            _ => (),
+                        }
+                    }
+                    (b"Audio", name) => {
+                        let audio = stat.audio.get_or_insert_with(Default::default);
+                        let text = reader.read_text(name, &mut Vec::new())?;
+                        match name {
+                            b"Bandwidth" => audio.bandwidth = parse_number(text)?,
+                            b"SampleRate" => audio.sample_rate = parse_number(text)?,
+                            b"Codec" => audio.codec = text,
+                            _ => (),
+                        }
+                    }
+                    _ => (),
+                },
+                Event::End(ref e) if e.name() == b"Video" || e.name() == b"Audio" => {
+                    section.clear()
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(stat)
+    }
+
+    fn get_live_channel_history<S>(&self, channel: S) -> Result<Vec<LiveRecord>, Error>
+    where
+        S: AsRef<str>,
+    {
+        let resources = "comp=history&live";
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: channel.as_ref(),
+            params: resources,
+            resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Bucket(BucketError::GetError {
+                msg: format!(
+                    "can not get live channel history, status code: {}",
+                    resp.status()
+                ),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        let mut records = vec![];
+        let mut cur = LiveRecord::default();
+        reader.trim_text(true);
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match e.name() {
+                    b"StartTime" => cur.start_time = reader.read_text(e.name(), &mut Vec::new())?,
+                    b"EndTime" => cur.end_time = reader.read_text(e.name(), &mut Vec::new())?,
+                    b"RemoteAddr" => {
+                        cur.remote_addr = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    _ => (),
+                },
+                Event::End(ref e) if e.name() == b"LiveRecord" => {
+                    records.push(std::mem::take(&mut cur))
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(records)
+    }
+
+    fn post_vod_playlist<S1, S2>(
+        &self,
+        channel: S1,
+        playlist: S2,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        let resources = format!("endTime={}&startTime={}&vod", end_time, start_time);
+        let object = format!("{}/{}", channel.as_ref(), playlist.as_ref());
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::POST,
+            bucket: self.bucket(),
+            object: &object,
+            params: &resources,
+            resources: &resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Bucket(BucketError::PutError {
+                msg: format!("can not post vod playlist, status code: {}", resp.status()),
+            }))
+        }
+    }
+
+    fn get_vod_playlist<S>(
+        &self,
+        channel: S,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<String, Error>
+    where
+        S: AsRef<str>,
+    {
+        let resources = format!("endTime={}&startTime={}&vod", end_time, start_time);
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: channel.as_ref(),
+            params: &resources,
+            resources: &resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            resp.text()
+        } else {
+            Err(Error::Bucket(BucketError::GetError {
+                msg: format!("can not get vod playlist, status code: {}", resp.status()),
+            }))
+        }
+    }
+}
+
+impl<'a> OSS<'a> {
+    /// Presigned `rtmp://` url to push to `channel`, valid until the unix
+    /// timestamp `expires`, an hour from now by default. `playlist_name`
+    /// overrides the channel's playlist for this push.
+    pub fn signed_rtmp_url<'b, U, P>(
+        &self,
+        channel: &str,
+        expires: U,
+        playlist_name: P,
+    ) -> Result<String, Error>
+    where
+        U: Into<Option<u64>>,
+        P: Into<Option<&'b str>>,
+    {
+        let expires = expires
+            .into()
+            .unwrap_or(self.now().timestamp() as u64 + 3600);
+        let params = playlist_name
+            .into()
+            .map(|name| vec![("playlistName", name)])
+            .unwrap_or_default();
+        Ok(self.oss_sign_rtmp_url(
+            self.key_id(),
+            self.key_secret(),
+            expires,
+            self.bucket(),
+            channel,
+            &params,
+        ))
+    }
+}
+
+fn get_list_live_channels_params_str(opts: &ListLiveChannelsOptions) -> String {
+    let params = [
+        ("marker", &opts.marker),
+        ("max-keys", &opts.max_keys),
+        ("prefix", &opts.prefix),
+    ];
+    let mut result = String::from("live");
+    for (k, v) in params.iter() {
+        if !v.is_empty() {
+            result += &format!("&{}={}", k, uri_encode(v, false));
+        }
+    }
+    result
+}
+
+fn parse_number<T: FromStr>(text: String) -> Result<T, Error> {
+    text.parse().map_err(|_| {
+        Error::Bucket(BucketError::GetError {
+            msg: format!("invalid number in live channel response: {}", text),
+        })
+    })
+}
@@ -0,0 +1,124 @@
+//! Content types guessed from object key extensions, for uploads that do
+//! not set one.
+
+use std::collections::HashMap;
+
+/// Sent when neither the caller nor the table knows better.
+pub const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+const BUILTIN: &[(&str, &str)] = &[
+    ("7z", "application/x-7z-compressed"),
+    ("aac", "audio/aac"),
+    ("apk", "application/vnd.android.package-archive"),
+    ("avi", "video/x-msvideo"),
+    ("avif", "image/avif"),
+    ("bin", "application/octet-stream"),
+    ("bmp", "image/bmp"),
+    ("bz2", "application/x-bzip2"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("eot", "application/vnd.ms-fontobject"),
+    ("epub", "application/epub+zip"),
+    ("exe", "application/octet-stream"),
+    ("flac", "audio/flac"),
+    ("flv", "video/x-flv"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/x-icon"),
+    ("ics", "text/calendar"),
+    ("jar", "application/java-archive"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "application/javascript"),
+    ("json", "application/json"),
+    ("m3u8", "application/x-mpegURL"),
+    ("m4a", "audio/mp4"),
+    ("md", "text/markdown"),
+    ("mid", "audio/midi"),
+    ("mjs", "application/javascript"),
+    ("mkv", "video/x-matroska"),
+    ("mov", "video/quicktime"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("mpeg", "video/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("otf", "font/otf"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("rar", "application/vnd.rar"),
+    ("rtf", "application/rtf"),
+    ("sh", "application/x-sh"),
+    ("svg", "image/svg+xml"),
+    ("tar", "application/x-tar"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("ts", "video/mp2t"),
+    ("ttf", "font/ttf"),
+    ("txt", "text/plain"),
+    ("wasm", "application/wasm"),
+    ("wav", "audio/wav"),
+    ("weba", "audio/webm"),
+    ("webm", "video/webm"),
+    ("webp", "image/webp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("xhtml", "application/xhtml+xml"),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("xml", "application/xml"),
+    ("yaml", "application/x-yaml"),
+    ("yml", "application/x-yaml"),
+    ("zip", "application/zip"),
+];
+
+/// Extension to content type table, the built-in one plus any overrides.
+#[derive(Debug, Clone, Default)]
+pub struct MimeTypes {
+    overrides: HashMap<String, String>,
+}
+
+impl MimeTypes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps `extension`, without the dot and case-insensitively, to
+    /// `content_type`, taking precedence over the built-in table.
+    pub fn insert<S1, S2>(&mut self, extension: S1, content_type: S2)
+    where
+        S1: AsRef<str>,
+        S2: Into<String>,
+    {
+        self.overrides
+            .insert(extension.as_ref().to_ascii_lowercase(), content_type.into());
+    }
+
+    /// Content type for the extension of `key`, if known.
+    pub fn guess(&self, key: &str) -> Option<&str> {
+        let name = key.rsplit('/').next().unwrap_or(key);
+        let (_, extension) = name.rsplit_once('.')?;
+        let extension = extension.to_ascii_lowercase();
+        if let Some(content_type) = self.overrides.get(&extension) {
+            return Some(content_type);
+        }
+        BUILTIN
+            .binary_search_by_key(&extension.as_str(), |(ext, _)| ext)
+            .ok()
+            .map(|idx| BUILTIN[idx].1)
+    }
+}
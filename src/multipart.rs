@@ -0,0 +1,290 @@
+use quick_xml::{events::Event, Reader};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH};
+use reqwest::Method;
+
+use super::crc64::{checksum, combine};
+use super::errors::{Error, ObjectError};
+use super::object::PutOptions;
+use super::oss::{SignedRequest, OSS};
+use super::transport::Response;
+use super::utils::encode_query;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UploadedPart {
+    pub part_number: u32,
+    pub e_tag: String,
+    /// Size and CRC64 of the part, used to check the completed object.
+    /// Without them for every part the check is skipped.
+    pub crc64: Option<(u64, u64)>,
+}
+
+impl UploadedPart {
+    pub fn new<S: Into<String>>(part_number: u32, e_tag: S) -> Self {
+        UploadedPart {
+            part_number,
+            e_tag: e_tag.into(),
+            crc64: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CompleteMultipartResponse {
+    pub e_tag: String,
+    pub location: String,
+    pub headers: HeaderMap,
+}
+
+pub trait MultipartAPI {
+    /// Starts a multipart upload and returns its upload id. Meta and headers
+    /// of the final object are taken from `opts`.
+    fn init_multipart_upload<'a, S, O>(&self, object_name: S, opts: O) -> Result<String, Error>
+    where
+        S: AsRef<str>,
+        O: Into<Option<&'a PutOptions<'a>>>;
+    /// Uploads one part, `part_number` starts at 1. The returned part is
+    /// passed on to `complete_multipart_upload`.
+    fn upload_part<S1, S2>(
+        &self,
+        buf: &[u8],
+        object_name: S1,
+        upload_id: S2,
+        part_number: u32,
+    ) -> Result<UploadedPart, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>;
+    fn complete_multipart_upload<S1, S2>(
+        &self,
+        object_name: S1,
+        upload_id: S2,
+        parts: &[UploadedPart],
+    ) -> Result<CompleteMultipartResponse, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>;
+    fn abort_multipart_upload<S1, S2>(&self, object_name: S1, upload_id: S2) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>;
+}
+
+impl<'a> MultipartAPI for OSS<'a> {
+    fn init_multipart_upload<'b, S, O>(&self, object_name: S, opts: O) -> Result<String, Error>
+    where
+        S: AsRef<str>,
+        O: Into<Option<&'b PutOptions<'b>>>,
+    {
+        let object_name = object_name.as_ref();
+        let (mut headers, content_type) = opts
+            .into()
+            .map(|_opts| (_opts.headers.clone(), _opts.content_type))
+            .unwrap_or_default();
+        self.set_content_type(&mut headers, content_type, object_name)?;
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::POST,
+            bucket: self.bucket(),
+            object: object_name,
+            params: "uploads",
+            resources: "uploads",
+            headers,
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Object(ObjectError::PutError {
+                msg: format!(
+                    "can not init multipart upload, status code: {}",
+                    resp.status()
+                ),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        reader.trim_text(true);
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) if e.name() == b"UploadId" => {
+                    return Ok(reader.read_text(e.name(), &mut Vec::new())?)
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Err(Error::Object(ObjectError::PutError {
+            msg: "UploadId not found in response".to_owned(),
+        }))
+    }
+
+    fn upload_part<S1, S2>(
+        &self,
+        buf: &[u8],
+        object_name: S1,
+        upload_id: S2,
+        part_number: u32,
+    ) -> Result<UploadedPart, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        let object_name = object_name.as_ref();
+        let resources = format!("partNumber={}&uploadId={}", part_number, upload_id.as_ref());
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, buf.len().to_string().parse()?);
+
+        let resp = self.send_upload(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: object_name,
+            params: &encode_query(&resources),
+            resources: &resources,
+            headers,
+            body: Some(buf.to_owned()),
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Object(ObjectError::PutError {
+                msg: format!(
+                    "can not upload part {}, status code: {}",
+                    part_number,
+                    resp.status()
+                ),
+            }));
+        }
+        let crc = checksum(buf);
+        self.verify_crc64(object_name, crc, resp.headers())?;
+        if let Some(listener) = self.progress_listener() {
+            listener.on_part(object_name, part_number, buf.len() as u64);
+        }
+        match resp.headers().get("ETag") {
+            Some(e_tag) => Ok(UploadedPart {
+                part_number,
+                e_tag: e_tag.to_str()?.to_owned(),
+                crc64: Some((buf.len() as u64, crc)),
+            }),
+            None => Err(Error::Object(ObjectError::PutError {
+                msg: format!("ETag of part {} not found in response", part_number),
+            })),
+        }
+    }
+
+    fn complete_multipart_upload<S1, S2>(
+        &self,
+        object_name: S1,
+        upload_id: S2,
+        parts: &[UploadedPart],
+    ) -> Result<CompleteMultipartResponse, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        let resp = self.send_complete_multipart_upload(
+            object_name.as_ref(),
+            upload_id.as_ref(),
+            parts,
+            HeaderMap::new(),
+        )?;
+        let mut result = CompleteMultipartResponse {
+            headers: resp.headers().to_owned(),
+            ..Default::default()
+        };
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        reader.trim_text(true);
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match e.name() {
+                    b"ETag" => result.e_tag = reader.read_text(e.name(), &mut Vec::new())?,
+                    b"Location" => result.location = reader.read_text(e.name(), &mut Vec::new())?,
+                    _ => (),
+                },
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(result)
+    }
+
+    fn abort_multipart_upload<S1, S2>(&self, object_name: S1, upload_id: S2) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        let resources = format!("uploadId={}", upload_id.as_ref());
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::DELETE,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: &encode_query(&resources),
+            resources: &resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Object(ObjectError::DeleteError {
+                msg: format!(
+                    "can not abort multipart upload, status code: {}",
+                    resp.status()
+                ),
+            }))
+        }
+    }
+}
+
+impl<'a> OSS<'a> {
+    /// Sends `CompleteMultipartUpload` with extra `headers` and checks the
+    /// combined CRC64, leaving the body to the caller.
+    pub(crate) fn send_complete_multipart_upload(
+        &self,
+        object_name: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+        mut headers: HeaderMap,
+    ) -> Result<Response, Error> {
+        let mut body = String::from("<CompleteMultipartUpload>");
+        for part in parts {
+            body += &format!(
+                "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                part.part_number, part.e_tag
+            );
+        }
+        body += "</CompleteMultipartUpload>";
+        let resources = format!("uploadId={}", upload_id);
+        headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::POST,
+            bucket: self.bucket(),
+            object: object_name,
+            params: &encode_query(&resources),
+            resources: &resources,
+            headers,
+            body: Some(body.into_bytes()),
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Object(ObjectError::PutError {
+                msg: format!(
+                    "can not complete multipart upload, status code: {}",
+                    resp.status()
+                ),
+            }));
+        }
+        // parts are joined in the order given
+        let crc = parts.iter().try_fold(0, |crc, part| {
+            part.crc64
+                .map(|(size, part_crc)| combine(crc, part_crc, size))
+        });
+        if let Some(crc) = crc {
+            self.verify_crc64(object_name, crc, resp.headers())?;
+        }
+        Ok(resp)
+    }
+}
@@ -1,14 +1,36 @@
+use chrono::{DateTime, Utc};
+use crypto::digest::Digest;
+use crypto::md5::Md5;
 use quick_xml::{events::Event, Reader};
-use reqwest::header::{HeaderMap, CONTENT_LENGTH, DATE};
+use reqwest::header::{
+    HeaderMap, CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    IF_UNMODIFIED_SINCE, LAST_MODIFIED, RANGE,
+};
+use reqwest::{Method, StatusCode};
 use std::collections::{binary_heap::Iter, HashMap};
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
 
-use super::auth::*;
+use super::crc64::{checksum, Crc64Writer};
 use super::errors::{Error, ObjectError};
-use super::oss::OSS;
+use super::oss::{SignedRequest, OSS};
+use super::progress::ProgressWriter;
+use super::sse::ServerSideEncryption;
+use super::throttle::{check_traffic_limit, ThrottledWriter, OSS_TRAFFIC_LIMIT};
+use super::transport::Response;
 use super::utils::*;
 
 pub const NULL_META: &[&str] = &[];
 
+const OSS_SYMLINK_TARGET: &str = "x-oss-symlink-target";
+const OSS_RESTORE: &str = "x-oss-restore";
+const OSS_VERSION_ID: &str = "x-oss-version-id";
+const OSS_DELETE_MARKER: &str = "x-oss-delete-marker";
+const OSS_COPY_SOURCE: &str = "x-oss-copy-source";
+const OSS_COPY_SOURCE_VERSION_ID: &str = "x-oss-copy-source-version-id";
+pub(crate) const OSS_HASH_CRC64: &str = "x-oss-hash-crc64ecma";
+
 #[derive(Debug, Clone)]
 pub struct GetObjResponse {
     pub content: String,
@@ -16,6 +38,18 @@ pub struct GetObjResponse {
     pub headers: HeaderMap,
 }
 
+impl GetObjResponse {
+    /// The `x-oss-version-id` of the returned object, if the bucket is versioned.
+    pub fn version_id(&self) -> Option<&str> {
+        header_str(&self.headers, OSS_VERSION_ID)
+    }
+
+    /// How the returned object is encrypted server-side.
+    pub fn encryption(&self) -> Option<ServerSideEncryption> {
+        ServerSideEncryption::from_headers(&self.headers)
+    }
+}
+
 impl GetBufferedObjResponse {
     pub fn new<S: AsRef<str>>(
         content: Vec<u8>,
@@ -35,6 +69,16 @@ impl GetBufferedObjResponse {
         };
         Ok(ret)
     }
+
+    /// The `x-oss-version-id` of the returned object, if the bucket is versioned.
+    pub fn version_id(&self) -> Option<&str> {
+        header_str(&self.headers, OSS_VERSION_ID)
+    }
+
+    /// How the returned object is encrypted server-side.
+    pub fn encryption(&self) -> Option<ServerSideEncryption> {
+        ServerSideEncryption::from_headers(&self.headers)
+    }
 }
 #[derive(Debug, Clone, Default)]
 pub struct GetBufferedObjResponse {
@@ -59,11 +103,249 @@ pub struct DetailObjects {
     size: String,
 }
 
+impl DetailObjects {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn last_modified(&self) -> &str {
+        &self.last_modified
+    }
+
+    pub fn e_tag(&self) -> &str {
+        &self.e_tag
+    }
+
+    pub fn size(&self) -> &str {
+        &self.size
+    }
+}
+
+/// Typed view of the headers returned by `head_object`.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectMeta {
+    pub content_length: u64,
+    pub e_tag: String,
+    pub last_modified: String,
+    pub crc64: Option<u64>,
+    pub version_id: Option<String>,
+    pub encryption: Option<ServerSideEncryption>,
+    pub meta: HashMap<String, String>,
+    pub headers: HeaderMap,
+}
+
+impl ObjectMeta {
+    pub fn from_headers(headers: HeaderMap) -> Result<Self, Error> {
+        let mut meta = HashMap::new();
+        for (key, val) in headers
+            .iter()
+            .filter(|(k, _)| k.as_str().starts_with(OSS_META_PREFIX))
+        {
+            meta.insert(
+                key.as_str().trim_start_matches(OSS_META_PREFIX).to_string(),
+                String::from_utf8(val.as_bytes().to_vec())?,
+            );
+        }
+        Ok(Self {
+            content_length: header_str(&headers, CONTENT_LENGTH.as_str())
+                .unwrap_or("0")
+                .parse()?,
+            e_tag: header_str(&headers, ETAG.as_str())
+                .unwrap_or_default()
+                .to_owned(),
+            last_modified: header_str(&headers, LAST_MODIFIED.as_str())
+                .unwrap_or_default()
+                .to_owned(),
+            crc64: header_str(&headers, OSS_HASH_CRC64)
+                .map(str::parse)
+                .transpose()?,
+            version_id: header_str(&headers, OSS_VERSION_ID).map(String::from),
+            encryption: ServerSideEncryption::from_headers(&headers),
+            meta,
+            headers,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ListVersionsOptions {
+    pub prefix: String,
+    pub key_marker: String,
+    pub version_id_marker: String,
+    pub delimiter: String,
+    pub max_keys: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ListVersionsResponse {
+    pub is_truncated: bool,
+    pub next_key_marker: String,
+    pub next_version_id_marker: String,
+    pub versions: Vec<ObjectVersion>,
+    pub delete_markers: Vec<ObjectVersion>,
+    pub prefixes: Vec<String>,
+}
+
+/// One entry of a version listing. Delete markers carry no `e_tag`, `size`
+/// or `storage_class`.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectVersion {
+    pub key: String,
+    pub version_id: String,
+    pub is_latest: bool,
+    pub last_modified: String,
+    pub e_tag: String,
+    pub size: String,
+    pub storage_class: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DeleteVersionResponse {
+    /// Version removed, or the id of the delete marker that was created.
+    pub version_id: Option<String>,
+    pub delete_marker: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CopyOptions {
+    /// Bucket to copy from, defaults to the destination bucket.
+    pub source_bucket: Option<String>,
+    pub source_version_id: Option<String>,
+    /// Server-side encryption of the copy.
+    pub encryption: Option<ServerSideEncryption>,
+    /// Extra headers, e.g. `x-oss-metadata-directive` or new meta.
+    pub headers: HeaderMap,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PutObjectResponse {
+    pub e_tag: String,
+    /// Version the put created, if the bucket is versioned.
+    pub version_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CopyObjectResponse {
+    pub e_tag: String,
+    pub last_modified: String,
+    pub version_id: Option<String>,
+    pub source_version_id: Option<String>,
+}
+
+/// A byte range of an object, both ends inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// `bytes=start-end`
+    FromTo(u64, u64),
+    /// `bytes=start-`, everything from `start` on.
+    From(u64),
+    /// `bytes=-n`, the last `n` bytes.
+    Last(u64),
+}
+
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ByteRange::FromTo(start, end) => write!(f, "bytes={}-{}", start, end),
+            ByteRange::From(start) => write!(f, "bytes={}-", start),
+            ByteRange::Last(n) => write!(f, "bytes=-{}", n),
+        }
+    }
+}
+
+/// Range and conditional headers for `get_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct GetOptions {
+    pub range: Option<ByteRange>,
+    pub if_match: Option<String>,
+    pub if_none_match: Option<String>,
+    pub if_modified_since: Option<DateTime<Utc>>,
+    pub if_unmodified_since: Option<DateTime<Utc>>,
+    pub version_id: Option<String>,
+    /// Server-side bandwidth limit in bit/s, see `throttle`.
+    pub traffic_limit: Option<u64>,
+    pub headers: HeaderMap,
+    pub params: String,
+}
+
+impl GetOptions {
+    fn to_headers(&self) -> Result<HeaderMap, Error> {
+        let mut headers = self.headers.clone();
+        if let Some(limit) = self.traffic_limit {
+            check_traffic_limit(limit)
+                .map_err(|msg| Error::Object(ObjectError::GetError { msg }))?;
+            headers.insert(OSS_TRAFFIC_LIMIT, limit.to_string().parse()?);
+        }
+        if let Some(range) = self.range {
+            headers.insert(RANGE, range.to_string().parse()?);
+        }
+        if let Some(etag) = &self.if_match {
+            headers.insert(IF_MATCH, etag.parse()?);
+        }
+        if let Some(etag) = &self.if_none_match {
+            headers.insert(IF_NONE_MATCH, etag.parse()?);
+        }
+        if let Some(date) = &self.if_modified_since {
+            headers.insert(IF_MODIFIED_SINCE, gmt_date(date).parse()?);
+        }
+        if let Some(date) = &self.if_unmodified_since {
+            headers.insert(IF_UNMODIFIED_SINCE, gmt_date(date).parse()?);
+        }
+        Ok(headers)
+    }
+}
+
+/// The `Content-Range` of a partial response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    pub start: u64,
+    pub end: u64,
+    pub total: u64,
+}
+
+impl FromStr for ContentRange {
+    type Err = Error;
+
+    /// Parses `bytes start-end/total`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = || -> Option<ContentRange> {
+            let (range, total) = s.trim().strip_prefix("bytes ")?.split_once('/')?;
+            let (start, end) = range.split_once('-')?;
+            Some(ContentRange {
+                start: start.parse().ok()?,
+                end: end.parse().ok()?,
+                total: total.parse().ok()?,
+            })
+        };
+        parse().ok_or_else(|| {
+            Error::Object(ObjectError::GetError {
+                msg: format!("invalid Content-Range header: {}", s),
+            })
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
+pub enum ConditionalGetResponse {
+    /// `200 OK`, the whole object.
+    Full(GetBufferedObjResponse),
+    /// `206 Partial Content`, the requested range.
+    Partial {
+        content_range: ContentRange,
+        response: GetBufferedObjResponse,
+    },
+    /// `304 Not Modified`, the cached copy is still valid.
+    NotModified { headers: HeaderMap },
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct PutOptions<'a> {
+    /// Guessed from the object key when empty, see `OSS::mime_types`.
     pub content_type: &'a str,
     pub headers: HeaderMap,
     pub params: String,
+    /// Send the body's `Content-MD5` and check it against the returned ETag.
+    pub content_md5: bool,
 }
 #[derive(Debug, Clone, Default)]
 pub struct ListOptions {
@@ -152,10 +434,128 @@ impl<'a> PutOptions<'a> {
             content_type: content_type.as_ref(),
             headers: header_map,
             params: param_string,
+            ..Default::default()
+        }
+    }
+
+    /// Has OSS throttle the upload to `bits_per_sec`, see `throttle`. Fails
+    /// outside `MIN_TRAFFIC_LIMIT..=MAX_TRAFFIC_LIMIT`.
+    pub fn with_traffic_limit(mut self, bits_per_sec: u64) -> Result<Self, Error> {
+        check_traffic_limit(bits_per_sec)
+            .map_err(|msg| Error::Object(ObjectError::PutError { msg }))?;
+        self.headers
+            .insert(OSS_TRAFFIC_LIMIT, bits_per_sec.to_string().parse()?);
+        Ok(self)
+    }
+
+    /// Lets OSS reject a body corrupted in transit, see `content_md5`.
+    pub fn with_content_md5(mut self) -> Self {
+        self.content_md5 = true;
+        self
+    }
+
+    /// Encrypts the object server-side, also for `init_multipart_upload`.
+    pub fn with_encryption(mut self, encryption: &ServerSideEncryption) -> Result<Self, Error> {
+        encryption.to_headers(&mut self.headers)?;
+        Ok(self)
+    }
+}
+
+/// Retrieval priority of a ColdArchive restore job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreTier {
+    Expedited,
+    Standard,
+    Bulk,
+}
+
+impl fmt::Display for RestoreTier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RestoreTier::Expedited => write!(f, "Expedited"),
+            RestoreTier::Standard => write!(f, "Standard"),
+            RestoreTier::Bulk => write!(f, "Bulk"),
+        }
+    }
+}
+
+/// State of an Archive/ColdArchive object, parsed from `x-oss-restore`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreStatus {
+    Ongoing,
+    Restored { expiry_date: DateTime<Utc> },
+}
+
+impl FromStr for RestoreStatus {
+    type Err = Error;
+
+    /// Parses values like `ongoing-request="false", expiry-date="Sun, 16 Apr 2017 08:12:33 GMT"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ongoing = None;
+        let mut expiry_date = None;
+        let mut rest = s.trim();
+        while !rest.is_empty() {
+            let eq = rest.find('=').ok_or_else(|| restore_header_error(s))?;
+            let key = rest[..eq].trim().trim_start_matches(',').trim();
+            let value = rest[eq + 1..].trim_start();
+            if !value.starts_with('"') {
+                return Err(restore_header_error(s));
+            }
+            let end = value[1..]
+                .find('"')
+                .ok_or_else(|| restore_header_error(s))?
+                + 1;
+            match key {
+                "ongoing-request" => ongoing = Some(&value[1..end] == "true"),
+                "expiry-date" => {
+                    expiry_date = Some(
+                        DateTime::parse_from_rfc2822(&value[1..end])
+                            .map_err(|_| restore_header_error(s))?
+                            .with_timezone(&Utc),
+                    )
+                }
+                _ => (),
+            }
+            rest = value[end + 1..].trim_start();
+        }
+        match (ongoing, expiry_date) {
+            (Some(true), _) => Ok(RestoreStatus::Ongoing),
+            (Some(false), Some(expiry_date)) => Ok(RestoreStatus::Restored { expiry_date }),
+            _ => Err(restore_header_error(s)),
         }
     }
 }
 
+/// Checks the ETag of a simple upload against the MD5 of what was sent.
+/// Multipart and KMS-encrypted objects have ETags of another form.
+fn verify_e_tag(object_name: &str, digest: &[u8], headers: &HeaderMap) -> Result<(), Error> {
+    let e_tag = header_str(headers, ETAG.as_str()).unwrap_or_default();
+    let e_tag = e_tag.trim_matches('"');
+    if e_tag.is_empty()
+        || e_tag.contains('-')
+        || header_str(headers, "x-oss-server-side-encryption") == Some("KMS")
+    {
+        return Ok(());
+    }
+    let expected: String = digest.iter().map(|b| format!("{:02X}", b)).collect();
+    if e_tag.eq_ignore_ascii_case(&expected) {
+        Ok(())
+    } else {
+        Err(Error::Object(ObjectError::PutError {
+            msg: format!(
+                "etag mismatch for {}, expected {} got {}",
+                object_name, expected, e_tag
+            ),
+        }))
+    }
+}
+
+fn restore_header_error(header: &str) -> Error {
+    Error::Object(ObjectError::RestoreError {
+        msg: format!("invalid {} header: {}", OSS_RESTORE, header),
+    })
+}
+
 impl Into<GetObjResponse> for GetBufferedObjResponse {
     fn into(mut self) -> GetObjResponse {
         GetObjResponse {
@@ -190,7 +590,30 @@ pub trait ObjectAPI {
         S2: AsRef<str>,
         P: Into<Option<HashMap<S2, Option<S2>>>>,
         M: Into<Vec<S2>>;
-    fn put<'a, S, O>(&self, buf: &[u8], object_name: S, opts: O) -> Result<(), Error>
+    /// Same as `get_as_buffer`, with range and conditional headers from
+    /// `opts`. A failed `If-Match`/`If-Unmodified-Since` is an error.
+    fn get_with_options<S1, S2, M>(
+        &self,
+        object_name: S1,
+        meta_keys: M,
+        opts: &GetOptions,
+    ) -> Result<ConditionalGetResponse, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        M: Into<Vec<S2>>;
+    /// Streams the object into `writer` and returns the number of bytes
+    /// written. On an integrity error the written data should be discarded.
+    fn get_to_writer<S, W>(&self, object_name: S, writer: &mut W) -> Result<u64, Error>
+    where
+        S: AsRef<str>,
+        W: Write + ?Sized;
+    fn put<'a, S, O>(
+        &self,
+        buf: &[u8],
+        object_name: S,
+        opts: O,
+    ) -> Result<PutObjectResponse, Error>
     where
         S: AsRef<str>,
         O: Into<Option<&'a PutOptions<'a>>>;
@@ -210,6 +633,143 @@ pub trait ObjectAPI {
     fn list_details<'a, O>(&self, opts: O) -> Result<ListDetailsResponse, Error>
     where
         O: Into<Option<&'a ListOptions>>;
+    /// Creates `symlink` pointing at `target`, no data is copied. Meta and
+    /// headers (e.g. `x-oss-object-acl`) are taken from `opts`.
+    fn put_symlink<'a, S1, S2, O>(&self, symlink: S1, target: S2, opts: O) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        O: Into<Option<&'a PutOptions<'a>>>;
+    /// Returns the key `symlink` points at.
+    fn get_symlink<S>(&self, symlink: S) -> Result<String, Error>
+    where
+        S: AsRef<str>;
+    /// Starts restoring an Archive or ColdArchive object for `days` days.
+    /// `tier` only applies to ColdArchive objects.
+    fn restore_object<S, T>(&self, object_name: S, days: u32, tier: T) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        T: Into<Option<RestoreTier>>;
+    /// Heads the object and returns its restore state, `None` when no
+    /// restore was ever requested.
+    fn restore_status<S>(&self, object_name: S) -> Result<Option<RestoreStatus>, Error>
+    where
+        S: AsRef<str>;
+    /// Heads the object and returns all of its headers, typed.
+    fn head_object<S>(&self, object_name: S) -> Result<ObjectMeta, Error>
+    where
+        S: AsRef<str>;
+    /// Same as `head_object`, for a specific version of the object.
+    fn head_version<S1, S2>(&self, object_name: S1, version_id: S2) -> Result<ObjectMeta, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>;
+    /// Permanently deletes one version of the object, or the delete marker
+    /// with that id.
+    fn del_version<S1, S2>(
+        &self,
+        object_name: S1,
+        version_id: S2,
+    ) -> Result<DeleteVersionResponse, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>;
+    fn list_object_versions<'a, O>(&self, opts: O) -> Result<ListVersionsResponse, Error>
+    where
+        O: Into<Option<&'a ListVersionsOptions>>;
+    fn copy_object<'a, S1, S2, O>(
+        &self,
+        src_object_name: S1,
+        dest_object_name: S2,
+        opts: O,
+    ) -> Result<CopyObjectResponse, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        O: Into<Option<&'a CopyOptions>>;
+    fn get_object_tagging<'a, S, V>(
+        &self,
+        object_name: S,
+        version_id: V,
+    ) -> Result<HashMap<String, String>, Error>
+    where
+        S: AsRef<str>,
+        V: Into<Option<&'a str>>;
+    fn put_object_tagging<'a, S1, S2, V>(
+        &self,
+        object_name: S1,
+        tags: HashMap<S2, S2>,
+        version_id: V,
+    ) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        V: Into<Option<&'a str>>;
+    fn delete_object_tagging<'a, S, V>(&self, object_name: S, version_id: V) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        V: Into<Option<&'a str>>;
+}
+
+fn header_str<'h>(headers: &'h HeaderMap, name: &str) -> Option<&'h str> {
+    headers.get(name).and_then(|val| val.to_str().ok())
+}
+
+/// Wraps `inner` to checksum, report and pace a download.
+fn receiver<'w, W: Write>(
+    oss: &'w OSS,
+    inner: W,
+    object_name: &'w str,
+    headers: &HeaderMap,
+) -> Crc64Writer<ProgressWriter<'w, ThrottledWriter<'w, W>>> {
+    Crc64Writer::new(ProgressWriter::new(
+        ThrottledWriter::new(inner, oss.rate_limiter()),
+        oss.progress_listener(),
+        object_name,
+        content_length(headers),
+    ))
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    header_str(headers, CONTENT_LENGTH.as_str())?.parse().ok()
+}
+
+fn with_version_id(query: &str, version_id: Option<&str>) -> String {
+    match version_id {
+        Some(version_id) if query.is_empty() => format!("versionId={}", version_id),
+        Some(version_id) => format!("{}&versionId={}", query, version_id),
+        None => query.to_owned(),
+    }
+}
+
+/// The sub-resources of an unencoded `query`, sorted the way they are signed.
+fn canonical_resources(query: &str) -> String {
+    let params: HashMap<&str, Option<&str>> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) => (k, Some(v)),
+            None => (pair, None),
+        })
+        .collect();
+    OSS::get_params_str(&params)
+}
+
+fn get_list_versions_params_str(opts: &ListVersionsOptions) -> String {
+    let params = [
+        ("delimiter", &opts.delimiter),
+        ("key-marker", &opts.key_marker),
+        ("max-keys", &opts.max_keys),
+        ("prefix", &opts.prefix),
+        ("version-id-marker", &opts.version_id_marker),
+    ];
+    let mut result = String::from("versions");
+    for (k, v) in params.iter() {
+        if !v.is_empty() {
+            result += &format!("&{}={}", k, uri_encode(v, false));
+        }
+    }
+    result
 }
 
 impl<'a> ObjectAPI for OSS<'a> {
@@ -246,28 +806,25 @@ impl<'a> ObjectAPI for OSS<'a> {
         } else {
             String::new()
         };
-        let host = self.host(self.bucket(), object_name, &params_string);
-        let date = self.date();
-
-        let mut headers = HeaderMap::new();
-        headers.insert(DATE, date.parse()?);
-        let authorization = self.oss_sign(
-            "GET",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object_name,
-            &params_string,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
-
-        let mut resp = self.client.get(&host).headers(headers).send()?;
-        let mut buf: Vec<u8> = vec![];
+        let mut resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: object_name,
+            params: &encode_query(&params_string),
+            resources: &params_string,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
 
         if resp.status().is_success() {
-            resp.copy_to(&mut buf)?;
-            GetBufferedObjResponse::new(buf, resp.headers().to_owned(), &meta_keys.into())
+            let mut writer = receiver(self, vec![], object_name, resp.headers());
+            resp.copy_to(&mut writer)?;
+            self.verify_crc64(object_name, writer.crc64(), resp.headers())?;
+            GetBufferedObjResponse::new(
+                writer.into_inner().into_inner().into_inner(),
+                resp.headers().to_owned(),
+                &meta_keys.into(),
+            )
         } else {
             Err(Error::Object(ObjectError::GetError {
                 msg: format!("can not get object, status code: {}", resp.status()).into(),
@@ -275,73 +832,113 @@ impl<'a> ObjectAPI for OSS<'a> {
         }
     }
 
-    fn put<'b, S, O>(&self, buf: &[u8], object_name: S, opts: O) -> Result<(), Error>
+    fn get_with_options<S1, S2, M>(
+        &self,
+        object_name: S1,
+        meta_keys: M,
+        opts: &GetOptions,
+    ) -> Result<ConditionalGetResponse, Error>
     where
-        S: AsRef<str>,
-        O: Into<Option<&'b PutOptions<'b>>>,
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        M: Into<Vec<S2>>,
     {
-        let object_name = object_name.as_ref();
-        let (params, mut headers) = if let Some(_opts) = opts.into() {
-            (&_opts.params[..], _opts.headers.clone())
-        } else {
-            ("", HeaderMap::new())
-        };
+        let query = with_version_id(&opts.params, opts.version_id.as_deref());
+        let mut resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: &encode_query(&query),
+            resources: &canonical_resources(&query),
+            headers: opts.to_headers()?,
+            body: None,
+        })?;
 
-        let host = self.host(self.bucket(), object_name, &params);
-        let date = self.date();
-
-        headers.insert(DATE, date.parse()?);
-        headers.insert(CONTENT_LENGTH, buf.len().to_string().parse()?);
-
-        let authorization = self.oss_sign(
-            "PUT",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object_name,
-            &params,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
-
-        let resp = self
-            .client
-            .put(&host)
-            .headers(headers)
-            .body(buf.to_owned())
-            .send()?;
-
-        if resp.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::Object(ObjectError::PutError {
-                msg: format!("can not put object, status code: {}", resp.status()).into(),
-            }))
+        match resp.status() {
+            StatusCode::OK | StatusCode::PARTIAL_CONTENT => {
+                let mut writer = receiver(self, vec![], object_name.as_ref(), resp.headers());
+                resp.copy_to(&mut writer)?;
+                let crc = writer.crc64();
+                let response = GetBufferedObjResponse::new(
+                    writer.into_inner().into_inner().into_inner(),
+                    resp.headers().to_owned(),
+                    &meta_keys.into(),
+                )?;
+                if resp.status() == StatusCode::OK {
+                    self.verify_crc64(object_name.as_ref(), crc, &response.headers)?;
+                    return Ok(ConditionalGetResponse::Full(response));
+                }
+                // the reported crc64 covers the whole object, not the range
+                let content_range = header_str(&response.headers, CONTENT_RANGE.as_str())
+                    .unwrap_or_default()
+                    .parse()?;
+                Ok(ConditionalGetResponse::Partial {
+                    content_range,
+                    response,
+                })
+            }
+            StatusCode::NOT_MODIFIED => Ok(ConditionalGetResponse::NotModified {
+                headers: resp.headers().to_owned(),
+            }),
+            status => Err(Error::Object(ObjectError::GetError {
+                msg: format!("can not get object, status code: {}", status),
+            })),
         }
     }
 
-    fn del<S>(&self, object_name: S) -> Result<(), Error>
+    fn get_to_writer<S, W>(&self, object_name: S, writer: &mut W) -> Result<u64, Error>
     where
         S: AsRef<str>,
+        W: Write + ?Sized,
     {
         let object_name = object_name.as_ref();
-        let host = self.host(self.bucket(), object_name, "");
-        let date = self.date();
+        let mut resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: object_name,
+            params: "",
+            resources: "",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+        if !resp.status().is_success() {
+            return Err(Error::Object(ObjectError::GetError {
+                msg: format!("can not get object, status code: {}", resp.status()),
+            }));
+        }
+        let mut writer = receiver(self, writer, object_name, resp.headers());
+        let len = resp.copy_to(&mut writer)?;
+        self.verify_crc64(object_name, writer.crc64(), resp.headers())?;
+        Ok(len)
+    }
 
-        let mut headers = HeaderMap::new();
-        headers.insert(DATE, date.parse()?);
-        let authorization = self.oss_sign(
-            "DELETE",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object_name,
-            "",
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
+    fn put<'b, S, O>(&self, buf: &[u8], object_name: S, opts: O) -> Result<PutObjectResponse, Error>
+    where
+        S: AsRef<str>,
+        O: Into<Option<&'b PutOptions<'b>>>,
+    {
+        let resp = self.put_object(buf, object_name.as_ref(), opts.into())?;
+        Ok(PutObjectResponse {
+            e_tag: header_str(resp.headers(), ETAG.as_str())
+                .unwrap_or_default()
+                .to_owned(),
+            version_id: header_str(resp.headers(), OSS_VERSION_ID).map(String::from),
+        })
+    }
 
-        let resp = self.client.delete(&host).headers(headers).send()?;
+    fn del<S>(&self, object_name: S) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::DELETE,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: "",
+            resources: "",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
 
         if resp.status().is_success() {
             Ok(())
@@ -364,24 +961,15 @@ impl<'a> ObjectAPI for OSS<'a> {
     where
         S: AsRef<str>,
     {
-        let object_name = object_name.as_ref();
-        let host = self.host(self.bucket(), object_name, "");
-        let date = self.date();
-
-        let mut headers = HeaderMap::new();
-        headers.insert(DATE, date.parse()?);
-        let authorization = self.oss_sign(
-            "HEAD",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object_name,
-            "",
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
-
-        let resp = self.client.head(&host).headers(headers).send()?;
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::HEAD,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: "",
+            resources: "",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
         if resp.status().is_success() {
             let mut ret = HashMap::new();
             for (key, val) in resp
@@ -407,23 +995,20 @@ impl<'a> ObjectAPI for OSS<'a> {
     {
         let (params_string, oss_resources) =
             OSS::get_list_2_params_str(&opts.into().unwrap_or(&ListOptions::default()));
-        let host = self.host(self.bucket(), "", &params_string);
-        let date = self.date();
-
-        let mut headers = HeaderMap::new();
-        headers.insert(DATE, date.parse()?);
-        let authorization = self.oss_sign(
-            "GET",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            "",
-            &oss_resources,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
-
-        let resp = self.client.get(&host).headers(headers).send()?;
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: "",
+            params: &params_string,
+            resources: &oss_resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+        if !resp.status().is_success() {
+            return Err(Error::Object(ObjectError::GetError {
+                msg: format!("can not list objects, status code: {}", resp.status()),
+            }));
+        }
         let xml_str = resp.text()?;
         let mut result = vec![];
         let mut reader = Reader::from_str(xml_str.as_str());
@@ -436,7 +1021,7 @@ impl<'a> ObjectAPI for OSS<'a> {
                     _ => (),
                 },
                 Ok(Event::Eof) => break,
-                Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+                Err(e) => return Err(e.into()),
                 _ => (),
             }
             buf.clear();
@@ -449,26 +1034,16 @@ impl<'a> ObjectAPI for OSS<'a> {
     {
         let (params_string, oss_resources) =
             OSS::get_list_2_params_str(&opts.into().unwrap_or(&ListOptions::default()));
-
-        let host = self.host(self.bucket(), "", &params_string);
-        let date = self.date();
-
-        let mut headers = HeaderMap::new();
-        headers.insert(DATE, date.parse()?);
-        let authorization = self.oss_sign(
-            "GET",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            "",
-            &oss_resources,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
-
-        let resp = self.client.get(&host).headers(headers).send()?;
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: "",
+            params: &params_string,
+            resources: &oss_resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
         let xml_str = resp.text()?;
-        println!("{}", xml_str);
         let mut result = ListDetailsResponse::default();
         let mut reader = Reader::from_str(xml_str.as_str());
         let mut buf = Vec::with_capacity(1000);
@@ -496,7 +1071,7 @@ impl<'a> ObjectAPI for OSS<'a> {
                         loop {
                             match reader.read_event(&mut buf) {
                                 Ok(Event::Start(ref e)) => match e.name() {
-                                    b"PreFix" => result
+                                    b"Prefix" => result
                                         .prefixes
                                         .push(reader.read_text(e.name(), &mut Vec::new())?),
                                     _ => {}
@@ -505,12 +1080,15 @@ impl<'a> ObjectAPI for OSS<'a> {
                                     b"CommonPrefixes" => break,
                                     _ => {}
                                 },
-                                _ => panic!(
-                                    "Error at position {}: {:?}",
-                                    reader.buffer_position(),
-                                    e
-                                ),
+                                Ok(Event::Eof) => {
+                                    return Err(Error::Object(ObjectError::GetError {
+                                        msg: "list response ends inside CommonPrefixes".to_string(),
+                                    }))
+                                }
+                                Err(e) => return Err(e.into()),
+                                _ => {}
                             }
+                            buf.clear();
                         }
                     }
                     _ => (),
@@ -522,11 +1100,532 @@ impl<'a> ObjectAPI for OSS<'a> {
                     _ => (),
                 },
                 Ok(Event::Eof) => break,
-                Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+                Err(e) => return Err(e.into()),
                 _ => (),
             }
             buf.clear();
         }
         Ok(result)
     }
+    fn put_symlink<'b, S1, S2, O>(&self, symlink: S1, target: S2, opts: O) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        O: Into<Option<&'b PutOptions<'b>>>,
+    {
+        let mut headers = opts
+            .into()
+            .map(|_opts| _opts.headers.clone())
+            .unwrap_or_default();
+        headers.insert(
+            OSS_SYMLINK_TARGET,
+            uri_encode(target.as_ref(), true).parse()?,
+        );
+
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: symlink.as_ref(),
+            params: "symlink",
+            resources: "symlink",
+            headers,
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Object(ObjectError::PutError {
+                msg: format!("can not put symlink, status code: {}", resp.status()),
+            }))
+        }
+    }
+    fn get_symlink<S>(&self, symlink: S) -> Result<String, Error>
+    where
+        S: AsRef<str>,
+    {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: symlink.as_ref(),
+            params: "symlink",
+            resources: "symlink",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Object(ObjectError::GetError {
+                msg: format!("can not get symlink, status code: {}", resp.status()),
+            }));
+        }
+        match resp.headers().get(OSS_SYMLINK_TARGET) {
+            Some(target) => Ok(percent_decode(target.to_str()?)),
+            None => Err(Error::Object(ObjectError::GetError {
+                msg: format!("{} not found in response", OSS_SYMLINK_TARGET),
+            })),
+        }
+    }
+    fn restore_object<S, T>(&self, object_name: S, days: u32, tier: T) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        T: Into<Option<RestoreTier>>,
+    {
+        let job_parameters = tier
+            .into()
+            .map(|tier| format!("<JobParameters><Tier>{}</Tier></JobParameters>", tier))
+            .unwrap_or_default();
+        let body = format!(
+            "<RestoreRequest><Days>{}</Days>{}</RestoreRequest>",
+            days, job_parameters
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::POST,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: "restore",
+            resources: "restore",
+            headers,
+            body: Some(body.into_bytes()),
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Object(ObjectError::RestoreError {
+                msg: format!("can not restore object, status code: {}", resp.status()),
+            }))
+        }
+    }
+    fn restore_status<S>(&self, object_name: S) -> Result<Option<RestoreStatus>, Error>
+    where
+        S: AsRef<str>,
+    {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::HEAD,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: "",
+            resources: "",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Object(ObjectError::HeadError {
+                msg: format!("can not head object, status code: {}", resp.status()),
+            }));
+        }
+        match resp.headers().get(OSS_RESTORE) {
+            Some(status) => Ok(Some(status.to_str()?.parse()?)),
+            None => Ok(None),
+        }
+    }
+    fn head_object<S>(&self, object_name: S) -> Result<ObjectMeta, Error>
+    where
+        S: AsRef<str>,
+    {
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::HEAD,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: "",
+            resources: "",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            ObjectMeta::from_headers(resp.headers().to_owned())
+        } else {
+            Err(Error::Object(ObjectError::HeadError {
+                msg: format!("can not head object, status code: {}", resp.status()),
+            }))
+        }
+    }
+    fn head_version<S1, S2>(&self, object_name: S1, version_id: S2) -> Result<ObjectMeta, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        let resources = with_version_id("", Some(version_id.as_ref()));
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::HEAD,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: &encode_query(&resources),
+            resources: &resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            ObjectMeta::from_headers(resp.headers().to_owned())
+        } else {
+            Err(Error::Object(ObjectError::HeadError {
+                msg: format!("can not head object, status code: {}", resp.status()),
+            }))
+        }
+    }
+    fn del_version<S1, S2>(
+        &self,
+        object_name: S1,
+        version_id: S2,
+    ) -> Result<DeleteVersionResponse, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        let resources = with_version_id("", Some(version_id.as_ref()));
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::DELETE,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: &encode_query(&resources),
+            resources: &resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            Ok(DeleteVersionResponse {
+                version_id: header_str(resp.headers(), OSS_VERSION_ID).map(String::from),
+                delete_marker: header_str(resp.headers(), OSS_DELETE_MARKER) == Some("true"),
+            })
+        } else {
+            Err(Error::Object(ObjectError::DeleteError {
+                msg: format!(
+                    "can not delete object version, status code: {}",
+                    resp.status()
+                ),
+            }))
+        }
+    }
+    fn list_object_versions<'b, O>(&self, opts: O) -> Result<ListVersionsResponse, Error>
+    where
+        O: Into<Option<&'b ListVersionsOptions>>,
+    {
+        let params_string =
+            get_list_versions_params_str(opts.into().unwrap_or(&ListVersionsOptions::default()));
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: "",
+            params: &params_string,
+            resources: "versions",
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Object(ObjectError::GetError {
+                msg: format!(
+                    "can not list object versions, status code: {}",
+                    resp.status()
+                ),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut result = ListVersionsResponse::default();
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::with_capacity(1000);
+        let mut cur_version = ObjectVersion::default();
+        let mut in_prefixes = false;
+        reader.trim_text(true);
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match e.name() {
+                    b"Version" | b"DeleteMarker" => cur_version = ObjectVersion::default(),
+                    b"CommonPrefixes" => in_prefixes = true,
+                    b"Prefix" if in_prefixes => result
+                        .prefixes
+                        .push(reader.read_text(e.name(), &mut Vec::new())?),
+                    b"Key" => cur_version.key = reader.read_text(e.name(), &mut Vec::new())?,
+                    b"VersionId" => {
+                        cur_version.version_id = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    b"IsLatest" => {
+                        cur_version.is_latest =
+                            reader.read_text(e.name(), &mut Vec::new())?.parse()?
+                    }
+                    b"LastModified" => {
+                        cur_version.last_modified = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    b"ETag" => cur_version.e_tag = reader.read_text(e.name(), &mut Vec::new())?,
+                    b"Size" => cur_version.size = reader.read_text(e.name(), &mut Vec::new())?,
+                    b"StorageClass" => {
+                        cur_version.storage_class = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    b"IsTruncated" => {
+                        result.is_truncated =
+                            reader.read_text(e.name(), &mut Vec::new())?.parse()?
+                    }
+                    b"NextKeyMarker" => {
+                        result.next_key_marker = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    b"NextVersionIdMarker" => {
+                        result.next_version_id_marker =
+                            reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    _ => (),
+                },
+                Event::End(ref e) => match e.name() {
+                    b"Version" => result.versions.push(std::mem::take(&mut cur_version)),
+                    b"DeleteMarker" => result.delete_markers.push(std::mem::take(&mut cur_version)),
+                    b"CommonPrefixes" => in_prefixes = false,
+                    _ => (),
+                },
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(result)
+    }
+    fn copy_object<'b, S1, S2, O>(
+        &self,
+        src_object_name: S1,
+        dest_object_name: S2,
+        opts: O,
+    ) -> Result<CopyObjectResponse, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        O: Into<Option<&'b CopyOptions>>,
+    {
+        let opts = opts.into();
+        let source_bucket = opts
+            .and_then(|_opts| _opts.source_bucket.as_deref())
+            .unwrap_or_else(|| self.bucket());
+        let source_version_id = opts.and_then(|_opts| _opts.source_version_id.as_deref());
+        let mut headers = opts.map(|_opts| _opts.headers.clone()).unwrap_or_default();
+        if let Some(encryption) = opts.and_then(|_opts| _opts.encryption.as_ref()) {
+            encryption.to_headers(&mut headers)?;
+        }
+        let mut copy_source = format!(
+            "/{}/{}",
+            source_bucket,
+            uri_encode(src_object_name.as_ref(), true)
+        );
+        if let Some(version_id) = source_version_id {
+            copy_source += &format!("?versionId={}", uri_encode(version_id, false));
+        }
+        headers.insert(OSS_COPY_SOURCE, copy_source.parse()?);
+
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: dest_object_name.as_ref(),
+            params: "",
+            resources: "",
+            headers,
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Object(ObjectError::CopyError {
+                msg: format!("can not copy object, status code: {}", resp.status()),
+            }));
+        }
+        let mut result = CopyObjectResponse {
+            version_id: header_str(resp.headers(), OSS_VERSION_ID).map(String::from),
+            source_version_id: header_str(resp.headers(), OSS_COPY_SOURCE_VERSION_ID)
+                .map(String::from),
+            ..Default::default()
+        };
+        let xml_str = resp.text()?;
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        reader.trim_text(true);
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match e.name() {
+                    b"ETag" => result.e_tag = reader.read_text(e.name(), &mut Vec::new())?,
+                    b"LastModified" => {
+                        result.last_modified = reader.read_text(e.name(), &mut Vec::new())?
+                    }
+                    _ => (),
+                },
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(result)
+    }
+    fn get_object_tagging<'b, S, V>(
+        &self,
+        object_name: S,
+        version_id: V,
+    ) -> Result<HashMap<String, String>, Error>
+    where
+        S: AsRef<str>,
+        V: Into<Option<&'b str>>,
+    {
+        let resources = with_version_id("tagging", version_id.into());
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: &encode_query(&resources),
+            resources: &resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Object(ObjectError::GetError {
+                msg: format!("can not get object tagging, status code: {}", resp.status()),
+            }));
+        }
+        let xml_str = resp.text()?;
+        let mut result = HashMap::new();
+        let mut reader = Reader::from_str(xml_str.as_str());
+        let mut buf = Vec::new();
+        let mut key = String::new();
+        let mut value = String::new();
+        reader.trim_text(true);
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => match e.name() {
+                    b"Key" => key = reader.read_text(e.name(), &mut Vec::new())?,
+                    b"Value" => value = reader.read_text(e.name(), &mut Vec::new())?,
+                    _ => (),
+                },
+                Event::End(ref e) if e.name() == b"Tag" => {
+                    result.insert(std::mem::take(&mut key), std::mem::take(&mut value));
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(result)
+    }
+    fn put_object_tagging<'b, S1, S2, V>(
+        &self,
+        object_name: S1,
+        tags: HashMap<S2, S2>,
+        version_id: V,
+    ) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        V: Into<Option<&'b str>>,
+    {
+        let mut body = String::from("<Tagging><TagSet>");
+        for (key, val) in tags.iter() {
+            body += &format!(
+                "<Tag><Key>{}</Key><Value>{}</Value></Tag>",
+                escape_xml(key.as_ref()),
+                escape_xml(val.as_ref())
+            );
+        }
+        body += "</TagSet></Tagging>";
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+
+        let resources = with_version_id("tagging", version_id.into());
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: &encode_query(&resources),
+            resources: &resources,
+            headers,
+            body: Some(body.into_bytes()),
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Object(ObjectError::PutError {
+                msg: format!("can not put object tagging, status code: {}", resp.status()),
+            }))
+        }
+    }
+    fn delete_object_tagging<'b, S, V>(&self, object_name: S, version_id: V) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        V: Into<Option<&'b str>>,
+    {
+        let resources = with_version_id("tagging", version_id.into());
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::DELETE,
+            bucket: self.bucket(),
+            object: object_name.as_ref(),
+            params: &encode_query(&resources),
+            resources: &resources,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Object(ObjectError::DeleteError {
+                msg: format!(
+                    "can not delete object tagging, status code: {}",
+                    resp.status()
+                ),
+            }))
+        }
+    }
+}
+
+impl<'a> OSS<'a> {
+    /// `ObjectAPI::put`, returning the response for callers that need its
+    /// body.
+    pub(crate) fn put_object(
+        &self,
+        buf: &[u8],
+        object_name: &str,
+        opts: Option<&PutOptions>,
+    ) -> Result<Response, Error> {
+        let (params, mut headers, content_type, content_md5) = if let Some(_opts) = opts {
+            (
+                &_opts.params[..],
+                _opts.headers.clone(),
+                _opts.content_type,
+                _opts.content_md5,
+            )
+        } else {
+            ("", HeaderMap::new(), "", false)
+        };
+        self.set_content_type(&mut headers, content_type, object_name)?;
+
+        headers.insert(CONTENT_LENGTH, buf.len().to_string().parse()?);
+        let mut digest = [0u8; 16];
+        if content_md5 {
+            let mut md5 = Md5::new();
+            md5.input(buf);
+            md5.result(&mut digest);
+            headers.insert("Content-MD5", base64::encode(digest).parse()?);
+        }
+
+        let resp = self.send_upload(SignedRequest {
+            verb: Method::PUT,
+            bucket: self.bucket(),
+            object: object_name,
+            params: &encode_query(params),
+            resources: params,
+            headers,
+            body: Some(buf.to_owned()),
+        })?;
+
+        if resp.status().is_success() {
+            if content_md5 {
+                verify_e_tag(object_name, &digest, resp.headers())?;
+            }
+            self.verify_crc64(object_name, checksum(buf), resp.headers())?;
+            Ok(resp)
+        } else {
+            Err(Error::Object(ObjectError::PutError {
+                msg: format!("can not put object, status code: {}", resp.status()).into(),
+            }))
+        }
+    }
 }
@@ -1,16 +1,72 @@
 use bytes::Bytes;
 use chrono::prelude::*;
-use reqwest::blocking::Client;
-use reqwest::header::{HeaderMap, DATE};
+use reqwest::blocking::{Body, Client, RequestBuilder};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, CONTENT_TYPE, DATE};
+use reqwest::{Method, StatusCode};
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::io::Cursor;
+use std::net::{IpAddr, Ipv6Addr};
 use std::str;
+use std::sync::Arc;
 
 use crate::prelude::ListOptions;
 
 use super::auth::*;
+use super::client::BucketHandle;
+use super::clock::{Clock, FixedClock, SystemClock};
+use super::crc64::{checksum, Crc64};
+use super::errors::{Error, IntegrityError};
+use super::mime::{MimeTypes, DEFAULT_CONTENT_TYPE};
+use super::object::OSS_HASH_CRC64;
+use super::progress::{async_progress_body, ProgressListener, ProgressReader};
+use super::throttle::{RateLimiter, ThrottledReader};
+use super::transport::{Response, Transport};
 use super::utils::*;
 
+/// How bucket and object are laid out in request urls. Signatures always
+/// cover `/{bucket}/{object}`, whatever the url looks like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Addressing {
+    /// `{bucket}.{endpoint}/{object}`, the default.
+    VirtualHosted,
+    /// `{endpoint}/{bucket}/{object}`, for OSS-compatible stand-ins.
+    PathStyle,
+    /// The endpoint is a custom domain bound to the bucket:
+    /// `{endpoint}/{object}`.
+    Cname,
+    /// The endpoint is an IPv4 or IPv6 address, addressed path-style.
+    Ip,
+}
+
+impl Addressing {
+    /// `Ip` for IP literal endpoints, `VirtualHosted` otherwise.
+    pub fn detect(endpoint: &str) -> Self {
+        let (_, authority) = split_scheme(endpoint);
+        let host = if let Some(bracketed) = authority.strip_prefix('[') {
+            bracketed.split(']').next().unwrap_or_default()
+        } else if authority.parse::<Ipv6Addr>().is_ok() {
+            authority
+        } else {
+            authority.split(':').next().unwrap_or_default()
+        };
+        if host.parse::<IpAddr>().is_ok() {
+            Addressing::Ip
+        } else {
+            Addressing::VirtualHosted
+        }
+    }
+}
+
+/// Splits `endpoint` into scheme, `http` if missing, and authority.
+fn split_scheme(endpoint: &str) -> (&str, &str) {
+    let endpoint = endpoint.trim_end_matches('/');
+    match endpoint.split_once("://") {
+        Some((scheme, authority)) => (scheme, authority),
+        None => ("http", endpoint),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct OSS<'a> {
     key_id: Cow<'a, str>,
@@ -18,9 +74,16 @@ pub struct OSS<'a> {
     endpoint: Cow<'a, str>,
     bucket: Cow<'a, str>,
     pub client: Client,
+    addressing: Addressing,
+    transport: Option<Arc<dyn Transport>>,
+    clock: Arc<dyn Clock>,
+    crc_check: bool,
+    mime_types: Arc<MimeTypes>,
+    progress: Option<Arc<dyn ProgressListener>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
-const RESOURCES: [&str; 51] = [
+const RESOURCES: [&str; 56] = [
     "acl",
     "uploads",
     "location",
@@ -72,19 +135,46 @@ const RESOURCES: [&str; 51] = [
     "callback",
     "callback-var",
     "continuation-token",
+    "versioning",
+    "versions",
+    "versionId",
+    "encryption",
+    "x-oss-traffic-limit",
 ];
 
+/// A request for `OSS::send_signed`.
+pub(crate) struct SignedRequest<'r> {
+    pub verb: Method,
+    pub bucket: &'r str,
+    pub object: &'r str,
+    /// Percent-encoded query string put on the url.
+    pub params: &'r str,
+    /// Unencoded part of `params` that takes part in the signature.
+    /// Both are usually the same, up to encoding.
+    pub resources: &'r str,
+    pub headers: HeaderMap,
+    pub body: Option<Vec<u8>>,
+}
+
 impl<'a> OSS<'a> {
     pub fn new<S>(key_id: S, key_secret: S, endpoint: S, bucket: S) -> Self
     where
         S: Into<Cow<'a, str>>,
     {
+        let endpoint = endpoint.into();
         OSS {
             key_id: key_id.into(),
             key_secret: key_secret.into(),
-            endpoint: endpoint.into(),
+            addressing: Addressing::detect(&endpoint),
+            endpoint,
             bucket: bucket.into(),
             client: reqwest::blocking::Client::new(),
+            transport: None,
+            clock: Arc::new(SystemClock),
+            crc_check: true,
+            mime_types: Arc::new(MimeTypes::new()),
+            progress: None,
+            rate_limiter: None,
         }
     }
 
@@ -104,28 +194,300 @@ impl<'a> OSS<'a> {
         &self.key_secret
     }
 
-    pub fn set_bucket(&mut self, bucket: &'a str) {
+    pub fn set_bucket<S>(&mut self, bucket: S)
+    where
+        S: Into<Cow<'a, str>>,
+    {
         self.bucket = bucket.into()
     }
 
+    /// A view of this instance on `bucket`, sharing its connection pool,
+    /// transport and clock. Nothing is copied or mutated, so any number of
+    /// handles can be used side by side.
+    pub fn with_bucket<'b>(&'b self, bucket: &'b str) -> BucketHandle<'b> {
+        BucketHandle::new(OSS {
+            key_id: Cow::Borrowed(&self.key_id),
+            key_secret: Cow::Borrowed(&self.key_secret),
+            endpoint: Cow::Borrowed(&self.endpoint),
+            bucket: Cow::Borrowed(bucket),
+            client: self.client.clone(),
+            addressing: self.addressing,
+            transport: self.transport.clone(),
+            clock: self.clock.clone(),
+            crc_check: self.crc_check,
+            mime_types: self.mime_types.clone(),
+            progress: self.progress.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+        })
+    }
+
+    /// Copies borrowed credentials and names, so the instance can outlive
+    /// them.
+    pub fn into_owned(self) -> OSS<'static> {
+        OSS {
+            key_id: Cow::Owned(self.key_id.into_owned()),
+            key_secret: Cow::Owned(self.key_secret.into_owned()),
+            endpoint: Cow::Owned(self.endpoint.into_owned()),
+            bucket: Cow::Owned(self.bucket.into_owned()),
+            client: self.client,
+            addressing: self.addressing,
+            transport: self.transport,
+            clock: self.clock,
+            crc_check: self.crc_check,
+            mime_types: self.mime_types,
+            progress: self.progress,
+            rate_limiter: self.rate_limiter,
+        }
+    }
+
+    pub fn addressing(&self) -> Addressing {
+        self.addressing
+    }
+
+    /// Overrides the addressing mode detected from the endpoint.
+    pub fn set_addressing(&mut self, addressing: Addressing) {
+        self.addressing = addressing
+    }
+
+    /// Routes all blocking requests through `transport` instead of sending
+    /// them with `client` directly, see `cassette`.
+    pub fn set_transport(&mut self, transport: Arc<dyn Transport>) {
+        self.transport = Some(transport)
+    }
+
+    /// Sets the time source for request dates and presigned url expiries,
+    /// see `clock`.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock
+    }
+
+    /// Makes `date()` always return `at`, so requests and their signatures
+    /// are reproducible.
+    pub fn freeze_time(&mut self, at: DateTime<Utc>) {
+        self.set_clock(Arc::new(FixedClock(at)))
+    }
+
+    /// Whether transferred data is checked against the CRC64 reported by
+    /// OSS, on by default.
+    pub fn crc_check(&self) -> bool {
+        self.crc_check
+    }
+
+    pub fn set_crc_check(&mut self, enabled: bool) {
+        self.crc_check = enabled
+    }
+
+    /// Fails with `Error::Integrity` if checking is on and `headers` report
+    /// a CRC64 other than `actual`.
+    pub(crate) fn verify_crc64(
+        &self,
+        object_name: &str,
+        actual: u64,
+        headers: &HeaderMap,
+    ) -> Result<(), Error> {
+        if !self.crc_check {
+            return Ok(());
+        }
+        let expected = match headers.get(OSS_HASH_CRC64) {
+            Some(expected) => expected.to_str()?.parse::<u64>()?,
+            None => return Ok(()),
+        };
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(Error::Integrity(IntegrityError {
+                object: object_name.to_owned(),
+                expected,
+                actual,
+            }))
+        }
+    }
+
+    /// Table used to guess the content type of uploads that set none.
+    pub fn mime_types(&self) -> &MimeTypes {
+        &self.mime_types
+    }
+
+    pub fn set_mime_types(&mut self, mime_types: MimeTypes) {
+        self.mime_types = Arc::new(mime_types)
+    }
+
+    /// Sets `Content-Type` from `content_type` or, if empty, from the
+    /// extension of `object_name`, unless `headers` already has one.
+    pub(crate) fn set_content_type(
+        &self,
+        headers: &mut HeaderMap,
+        content_type: &str,
+        object_name: &str,
+    ) -> Result<(), Error> {
+        if headers.contains_key(CONTENT_TYPE) {
+            return Ok(());
+        }
+        let content_type = match content_type {
+            "" => self
+                .mime_types
+                .guess(object_name)
+                .unwrap_or(DEFAULT_CONTENT_TYPE),
+            content_type => content_type,
+        };
+        headers.insert(CONTENT_TYPE, content_type.parse()?);
+        Ok(())
+    }
+
+    /// Reports the progress of puts, gets, multipart uploads and file
+    /// downloads to `listener`.
+    pub fn set_progress_listener(&mut self, listener: Arc<dyn ProgressListener>) {
+        self.progress = Some(listener)
+    }
+
+    /// A copy without progress listener, for requests that are one piece of
+    /// a transfer reported as a whole.
+    pub(crate) fn without_progress(&self) -> OSS<'a> {
+        OSS {
+            progress: None,
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn progress_listener(&self) -> Option<&Arc<dyn ProgressListener>> {
+        self.progress.as_ref()
+    }
+
+    pub(crate) fn report_progress(&self, object_name: &str, transferred: u64, total: Option<u64>) {
+        if let Some(listener) = &self.progress {
+            listener.on_progress(object_name, transferred, total);
+        }
+    }
+
+    /// Paces the blocking transfers of this instance, and of clients and
+    /// handles made from it, with `limiter`.
+    pub fn set_rate_limiter(&mut self, limiter: Arc<RateLimiter>) {
+        self.rate_limiter = Some(limiter)
+    }
+
+    pub(crate) fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_deref()
+    }
+
+    pub(crate) fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Sends the request and reports whether it failed with
+    /// `RequestTimeTooSkewed` and the clock corrected itself.
+    fn dispatch(&self, req: RequestBuilder) -> Result<(Response, bool), Error> {
+        let req = req.build()?;
+        let resp = match &self.transport {
+            Some(transport) => transport.send(&self.client, req)?,
+            None => self.client.execute(req)?.into(),
+        };
+        if resp.status() != StatusCode::FORBIDDEN {
+            return Ok((resp, false));
+        }
+
+        let status = resp.status();
+        let headers = resp.headers().to_owned();
+        let body = resp.bytes()?;
+        let skewed = str::from_utf8(&body)
+            .is_ok_and(|text| text.contains("<Code>RequestTimeTooSkewed</Code>"))
+            && headers
+                .get(DATE)
+                .and_then(|date| date.to_str().ok())
+                .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+                .is_some_and(|date| self.clock.correct(date.with_timezone(&Utc)));
+        Ok((Response::new(status, headers, Cursor::new(body)), skewed))
+    }
+
+    /// Url of `object` in `bucket`. The key is percent-encoded here,
+    /// `resources_str` must already be.
     pub fn host(&self, bucket: &str, object: &str, resources_str: &str) -> String {
-        if self.endpoint.starts_with("https") {
-            format!(
-                "https://{}.{}/{}?{}",
-                bucket,
-                self.endpoint.replacen("https://", "", 1),
-                object,
-                resources_str
-            )
+        let object = uri_encode(object, true);
+        let (scheme, authority) = split_scheme(&self.endpoint);
+        let authority = if authority.parse::<Ipv6Addr>().is_ok() {
+            Cow::Owned(format!("[{}]", authority))
         } else {
-            format!(
-                "http://{}.{}/{}?{}",
+            Cow::Borrowed(authority)
+        };
+        match self.addressing {
+            _ if bucket.is_empty() => {
+                format!("{}://{}/{}?{}", scheme, authority, object, resources_str)
+            }
+            Addressing::VirtualHosted => format!(
+                "{}://{}.{}/{}?{}",
+                scheme, bucket, authority, object, resources_str
+            ),
+            Addressing::Cname => {
+                format!("{}://{}/{}?{}", scheme, authority, object, resources_str)
+            }
+            Addressing::PathStyle | Addressing::Ip => format!(
+                "{}://{}/{}/{}?{}",
+                scheme, authority, bucket, object, resources_str
+            ),
+        }
+    }
+
+    /// Signs the headers of `req` and sends it. A request rejected for
+    /// clock skew is signed and sent once more if the clock corrected
+    /// itself.
+    pub(crate) fn send_signed(&self, req: SignedRequest) -> Result<Response, Error> {
+        self.send(req, false)
+    }
+
+    /// `send_signed` for object data, streaming the body through the rate
+    /// limiter and reporting the progress of `req.object`.
+    pub(crate) fn send_upload(&self, req: SignedRequest) -> Result<Response, Error> {
+        self.send(req, true)
+    }
+
+    fn send(&self, req: SignedRequest, upload: bool) -> Result<Response, Error> {
+        let SignedRequest {
+            verb,
+            bucket,
+            object,
+            params,
+            resources,
+            headers,
+            body,
+        } = req;
+        let host = self.host(bucket, object, params);
+        let mut retried = false;
+        loop {
+            let mut headers = headers.clone();
+            headers.insert(DATE, self.date().parse()?);
+            let authorization = self.oss_sign(
+                verb.as_str(),
+                self.key_id(),
+                self.key_secret(),
                 bucket,
-                self.endpoint.replacen("http://", "", 1),
                 object,
-                resources_str
-            )
+                resources,
+                &headers,
+            );
+            headers.insert("Authorization", authorization.parse()?);
+
+            // the body is rebuilt for every attempt, a stream is sent once
+            let mut req = self.client.request(verb.clone(), &host).headers(headers);
+            if let Some(body) = &body {
+                req = req.body(self.request_body(body.clone(), object, upload));
+            }
+            match self.dispatch(req)? {
+                (_, true) if !retried => retried = true,
+                (resp, _) => return Ok(resp),
+            }
+        }
+    }
+
+    /// Uploads go out chunk by chunk when they are paced or reported, other
+    /// bodies in one piece.
+    fn request_body(&self, body: Vec<u8>, object: &str, upload: bool) -> Body {
+        if !upload || (self.rate_limiter.is_none() && self.progress.is_none()) {
+            return body.into();
         }
+        let len = body.len() as u64;
+        let reader = ThrottledReader::new(Cursor::new(body), self.rate_limiter.clone());
+        let reader =
+            ProgressReader::new(reader, self.progress.clone(), object.to_owned(), Some(len));
+        Body::sized(reader, len)
     }
 
     pub fn signiture_url<U>(
@@ -133,28 +495,51 @@ impl<'a> OSS<'a> {
         object: &str,
         expires: U,
         verb: &str,
-    ) -> Result<String, super::errors::Error>
+    ) -> Result<String, Error>
     where
         U: Into<Option<u64>>,
     {
+        self.signiture_url_with_params(object, expires, verb, None::<HashMap<&str, Option<&str>>>)
+    }
+
+    /// Same as `signiture_url`, with signed query params such as
+    /// `x-oss-traffic-limit` or `x-oss-process` in the url.
+    pub fn signiture_url_with_params<U, S, P>(
+        &self,
+        object: &str,
+        expires: U,
+        verb: &str,
+        params: P,
+    ) -> Result<String, Error>
+    where
+        U: Into<Option<u64>>,
+        S: AsRef<str>,
+        P: Into<Option<HashMap<S, Option<S>>>>,
+    {
+        let resources = params
+            .into()
+            .map(|params| self.get_resources_str(&params))
+            .unwrap_or_default();
         let date = self.date();
 
         let mut headers = HeaderMap::new();
         headers.insert(DATE, date.parse()?);
-        Ok(self.oss_sign_url(
+        let target = UrlTarget {
             verb,
+            bucket: self.bucket(),
+            object,
+            oss_resources: &resources,
+        };
+        Ok(self.oss_sign_url(
+            &target,
             self.key_id(),
             self.key_secret(),
             expires.into(),
-            self.bucket(),
-            object,
-            "",
             &headers,
         ))
     }
     pub fn date(&self) -> String {
-        let now: DateTime<Utc> = Utc::now();
-        now.format("%a, %d %b %Y %T GMT").to_string()
+        gmt_date(&self.now())
     }
 
     pub fn get_params_str<S>(params: &HashMap<S, Option<S>>) -> String
@@ -182,6 +567,7 @@ impl<'a> OSS<'a> {
     pub fn get_list_2_params_str(opts: &ListOptions) -> (String, String) {
         let mut params: Vec<(&str, &str)> = vec![];
         params.push(("continuation-token", &opts.marker));
+        // signed unencoded, sent encoded
         let resources = if opts.marker.is_empty() {
             String::new()
         } else {
@@ -194,7 +580,7 @@ impl<'a> OSS<'a> {
         for (k, v) in params {
             if !v.is_empty() {
                 result += "&";
-                result += &format!("{}={}", k, v);
+                result += &format!("{}={}", k, uri_encode(v, false));
             }
         }
         (result, resources)
@@ -222,12 +608,14 @@ impl<'a> OSS<'a> {
         result
     }
 
+    /// Async `get`. Unlike the blocking calls it goes straight to the
+    /// network, without transport, rate limiter or skew retry.
     pub async fn async_get_object<S>(
         &self,
         object: S,
         headers: Option<HashMap<S, S>>,
         resources: Option<HashMap<S, Option<S>>>,
-    ) -> Result<Bytes, reqwest::Error>
+    ) -> Result<Bytes, Error>
     where
         S: AsRef<str>,
     {
@@ -237,14 +625,14 @@ impl<'a> OSS<'a> {
         } else {
             String::new()
         };
-        let host = self.host(self.bucket(), object, &resources_str);
+        let host = self.host(self.bucket(), object, &encode_query(&resources_str));
         let date = self.date();
         let mut headers = if let Some(h) = headers {
-            to_headers(h).unwrap()
+            to_headers(h)?
         } else {
             HeaderMap::new()
         };
-        headers.insert(DATE, date.parse().unwrap());
+        headers.insert(DATE, date.parse()?);
         let authorization = self.oss_sign(
             "GET",
             self.key_id(),
@@ -254,23 +642,37 @@ impl<'a> OSS<'a> {
             &resources_str,
             &headers,
         );
-        headers.insert("Authorization", authorization.parse().unwrap());
+        headers.insert("Authorization", authorization.parse()?);
 
-        let res = reqwest::Client::new()
+        let mut res = reqwest::Client::new()
             .get(&host)
             .headers(headers)
             .send()
             .await?;
-        Ok(res.bytes().await?)
+        let total = res.content_length();
+        let mut buf = Vec::with_capacity(total.unwrap_or_default() as usize);
+        let mut crc = Crc64::new();
+        self.report_progress(object, 0, total);
+        while let Some(chunk) = res.chunk().await? {
+            crc.update(&chunk);
+            buf.extend_from_slice(&chunk);
+            self.report_progress(object, buf.len() as u64, total);
+        }
+        // the reported crc64 covers the whole object, not a range
+        if res.status() == StatusCode::OK {
+            self.verify_crc64(object, crc.finish(), res.headers())?;
+        }
+        Ok(Bytes::from(buf))
     }
 
+    /// Async `put`, sent like `async_get_object`.
     pub async fn async_put_object_from_buffer<S1, S2, H, R>(
         &self,
         buf: &[u8],
         object: S1,
         headers: H,
         resources: R,
-    ) -> Result<Bytes, reqwest::Error>
+    ) -> Result<Bytes, Error>
     where
         S1: AsRef<str>,
         S2: AsRef<str>,
@@ -283,15 +685,15 @@ impl<'a> OSS<'a> {
         } else {
             String::new()
         };
-        let host = self.host(self.bucket(), object, &resources_str);
+        let host = self.host(self.bucket(), object, &encode_query(&resources_str));
         let date = self.date();
 
         let mut headers = if let Some(h) = headers.into() {
-            to_headers(h).unwrap()
+            to_headers(h)?
         } else {
             HeaderMap::new()
         };
-        headers.insert(DATE, date.parse().unwrap());
+        headers.insert(DATE, date.parse()?);
         let authorization = self.oss_sign(
             "PUT",
             self.key_id(),
@@ -301,14 +703,19 @@ impl<'a> OSS<'a> {
             &resources_str,
             &headers,
         );
-        headers.insert("Authorization", authorization.parse().unwrap());
+        headers.insert("Authorization", authorization.parse()?);
 
+        headers.insert(CONTENT_LENGTH, buf.len().into());
+        let body = async_progress_body(buf, self.progress.clone(), object.to_owned());
         let res = reqwest::Client::new()
             .put(&host)
             .headers(headers)
-            .body(buf.to_owned())
+            .body(body)
             .send()
             .await?;
+        if res.status().is_success() {
+            self.verify_crc64(object, checksum(buf), res.headers())?;
+        }
         Ok(res.bytes().await?)
     }
 }
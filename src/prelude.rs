@@ -1,3 +1,19 @@
+pub use super::bucket::*;
+pub use super::callback::{Callback, CallbackAPI, CallbackBodyType, CallbackVerifier};
+pub use super::client::{BucketClient, BucketHandle, OssClient};
+pub use super::download::*;
+pub use super::encryption::{
+    AesKeyProvider, EncryptedClient, KeyProvider, RsaKeyProvider, RsaPadding,
+};
+pub use super::image::*;
+pub use super::live::*;
+pub use super::mime::MimeTypes;
+pub use super::multipart::*;
 pub use super::object::*;
-pub use super::oss::OSS;
+pub use super::oss::{Addressing, OSS};
+pub use super::progress::ProgressListener;
+pub use super::select::*;
 pub use super::service::*;
+pub use super::sse::*;
+pub use super::throttle::RateLimiter;
+pub use super::website::*;
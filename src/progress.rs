@@ -0,0 +1,139 @@
+//! Progress reporting for uploads and downloads, see
+//! `OSS::set_progress_listener`.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures_util::stream::{self, StreamExt};
+
+/// Size of the chunks async uploads are reported in.
+const ASYNC_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Receives transfer progress. Calls may come from several threads at once
+/// during parallel downloads.
+pub trait ProgressListener: Send + Sync + fmt::Debug {
+    /// `transferred` bytes of `object_name` are done, out of `total` when
+    /// it is known. Multipart uploads report every part on its own.
+    fn on_progress(&self, object_name: &str, transferred: u64, total: Option<u64>);
+
+    /// Part `part_number` of `size` bytes finished, for multipart uploads
+    /// and ranged downloads. Parts are numbered from 1.
+    fn on_part(&self, _object_name: &str, _part_number: u32, _size: u64) {}
+}
+
+/// Passes writes through to `inner`, reporting the running byte count.
+pub(crate) struct ProgressWriter<'a, W> {
+    inner: W,
+    listener: Option<&'a Arc<dyn ProgressListener>>,
+    object_name: &'a str,
+    transferred: u64,
+    total: Option<u64>,
+}
+
+impl<'a, W: Write> ProgressWriter<'a, W> {
+    pub(crate) fn new(
+        inner: W,
+        listener: Option<&'a Arc<dyn ProgressListener>>,
+        object_name: &'a str,
+        total: Option<u64>,
+    ) -> Self {
+        if let Some(listener) = listener {
+            listener.on_progress(object_name, 0, total);
+        }
+        ProgressWriter {
+            inner,
+            listener,
+            object_name,
+            transferred: 0,
+            total,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<'a, W: Write> Write for ProgressWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.transferred += n as u64;
+        if let Some(listener) = self.listener {
+            listener.on_progress(self.object_name, self.transferred, self.total);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Passes reads through from `inner`, reporting the running byte count.
+/// Owns its listener so it can be a request body.
+pub(crate) struct ProgressReader<R> {
+    inner: R,
+    listener: Option<Arc<dyn ProgressListener>>,
+    object_name: String,
+    transferred: u64,
+    total: Option<u64>,
+}
+
+impl<R: Read> ProgressReader<R> {
+    pub(crate) fn new(
+        inner: R,
+        listener: Option<Arc<dyn ProgressListener>>,
+        object_name: String,
+        total: Option<u64>,
+    ) -> Self {
+        if let Some(listener) = &listener {
+            listener.on_progress(&object_name, 0, total);
+        }
+        ProgressReader {
+            inner,
+            listener,
+            object_name,
+            transferred: 0,
+            total,
+        }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.transferred += n as u64;
+        if let Some(listener) = &self.listener {
+            listener.on_progress(&self.object_name, self.transferred, self.total);
+        }
+        Ok(n)
+    }
+}
+
+/// An async request body sending `data` in chunks and reporting each one
+/// as the client takes it.
+pub(crate) fn async_progress_body(
+    data: &[u8],
+    listener: Option<Arc<dyn ProgressListener>>,
+    object_name: String,
+) -> reqwest::Body {
+    let total = Some(data.len() as u64);
+    if let Some(listener) = &listener {
+        listener.on_progress(&object_name, 0, total);
+    }
+    let chunks: Vec<Bytes> = data
+        .chunks(ASYNC_CHUNK_SIZE)
+        .map(Bytes::copy_from_slice)
+        .collect();
+    let mut transferred = 0;
+    let chunks = stream::iter(chunks).map(move |chunk| {
+        transferred += chunk.len() as u64;
+        if let Some(listener) = &listener {
+            listener.on_progress(&object_name, transferred, total);
+        }
+        Ok::<_, io::Error>(chunk)
+    });
+    reqwest::Body::wrap_stream(chunks)
+}
@@ -0,0 +1,566 @@
+//! SelectObject: SQL over CSV and JSON objects.
+//!
+//! Results arrive as a stream of binary frames, each a 12 byte header
+//! (version, type, payload length, header checksum), the payload and a
+//! CRC32 of the data it carries. `SelectRecords` decodes them lazily and
+//! yields one record at a time.
+
+use base64::encode;
+use reqwest::header::{HeaderMap, CONTENT_LENGTH};
+use reqwest::Method;
+use std::io::Read;
+
+use super::errors::{Error, ObjectError};
+use super::oss::{SignedRequest, OSS};
+use super::transport::Response;
+use super::utils::{encode_query, escape_xml};
+
+pub(crate) const DATA_FRAME: u32 = 0x80_0001;
+pub(crate) const CONTINUOUS_FRAME: u32 = 0x80_0004;
+pub(crate) const END_FRAME: u32 = 0x80_0005;
+pub(crate) const CSV_META_END_FRAME: u32 = 0x80_0006;
+pub(crate) const JSON_META_END_FRAME: u32 = 0x80_0007;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileHeaderInfo {
+    /// The first line is data.
+    None,
+    /// The first line is a header and skipped.
+    Ignore,
+    /// The first line is a header, its names can be used in the query.
+    Use,
+}
+
+impl FileHeaderInfo {
+    fn as_str(self) -> &'static str {
+        match self {
+            FileHeaderInfo::None => "None",
+            FileHeaderInfo::Ignore => "Ignore",
+            FileHeaderInfo::Use => "Use",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvInput {
+    pub file_header_info: FileHeaderInfo,
+    pub record_delimiter: String,
+    pub field_delimiter: String,
+    pub quote_character: String,
+    pub comment_character: String,
+    /// `line-range=start-end` or `split-range=start-end`, the latter needs
+    /// `create_select_object_meta` first.
+    pub range: Option<String>,
+    pub allow_quoted_record_delimiter: bool,
+}
+
+impl Default for CsvInput {
+    fn default() -> Self {
+        CsvInput {
+            file_header_info: FileHeaderInfo::None,
+            record_delimiter: "\n".to_owned(),
+            field_delimiter: ",".to_owned(),
+            quote_character: "\"".to_owned(),
+            comment_character: "#".to_owned(),
+            range: None,
+            allow_quoted_record_delimiter: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonType {
+    /// The object is a single JSON document.
+    Document,
+    /// One JSON document per line.
+    Lines,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonInput {
+    pub json_type: JsonType,
+    /// Same as `CsvInput::range`, `Lines` only.
+    pub range: Option<String>,
+    pub parse_json_number_as_string: bool,
+}
+
+impl JsonInput {
+    pub fn new(json_type: JsonType) -> Self {
+        JsonInput {
+            json_type,
+            range: None,
+            parse_json_number_as_string: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SelectInput {
+    Csv(CsvInput),
+    Json(JsonInput),
+}
+
+impl SelectInput {
+    fn format(&self) -> &'static str {
+        match self {
+            SelectInput::Csv(_) => "csv",
+            SelectInput::Json(_) => "json",
+        }
+    }
+
+    /// `<InputSerialization>`, `meta` leaves out what meta requests reject.
+    fn to_xml(&self, gzip: bool, meta: bool) -> String {
+        let mut xml = format!(
+            "<InputSerialization><CompressionType>{}</CompressionType>",
+            if gzip { "GZIP" } else { "None" }
+        );
+        match self {
+            SelectInput::Csv(csv) => {
+                xml += "<CSV>";
+                if !meta {
+                    xml += &format!(
+                        "<FileHeaderInfo>{}</FileHeaderInfo><CommentCharacter>{}</CommentCharacter>",
+                        csv.file_header_info.as_str(),
+                        encode(&csv.comment_character)
+                    );
+                }
+                xml += &format!(
+                    "<RecordDelimiter>{}</RecordDelimiter><FieldDelimiter>{}</FieldDelimiter><QuoteCharacter>{}</QuoteCharacter>",
+                    encode(&csv.record_delimiter),
+                    encode(&csv.field_delimiter),
+                    encode(&csv.quote_character)
+                );
+                if !meta {
+                    if let Some(range) = &csv.range {
+                        xml += &format!("<Range>{}</Range>", escape_xml(range));
+                    }
+                    xml += &format!(
+                        "<AllowQuotedRecordDelimiter>{}</AllowQuotedRecordDelimiter>",
+                        csv.allow_quoted_record_delimiter
+                    );
+                }
+                xml += "</CSV>";
+            }
+            SelectInput::Json(json) => {
+                let json_type = match json.json_type {
+                    JsonType::Document => "DOCUMENT",
+                    JsonType::Lines => "LINES",
+                };
+                xml += &format!("<JSON><Type>{}</Type>", json_type);
+                if !meta {
+                    if let Some(range) = &json.range {
+                        xml += &format!("<Range>{}</Range>", escape_xml(range));
+                    }
+                    xml += &format!(
+                        "<ParseJsonNumberAsString>{}</ParseJsonNumberAsString>",
+                        json.parse_json_number_as_string
+                    );
+                }
+                xml += "</JSON>";
+            }
+        }
+        xml + "</InputSerialization>"
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SelectRequest {
+    /// The SQL, e.g. `select * from ossobject where _1 > 100`.
+    pub expression: String,
+    pub input: SelectInput,
+    /// The object is gzip-compressed.
+    pub gzip: bool,
+    /// Ends every output record, also used to split the records yielded.
+    pub output_record_delimiter: String,
+    /// CSV only.
+    pub output_field_delimiter: String,
+    /// CSV only, also output the columns not selected, as empty fields.
+    pub keep_all_columns: bool,
+    /// CSV only, start with the header line of the object.
+    pub output_header: bool,
+    pub skip_partial_data_record: bool,
+    pub max_skipped_records_allowed: Option<u64>,
+    /// Have OSS send a CRC32 for every data frame, checked on receipt.
+    pub enable_payload_crc: bool,
+}
+
+impl SelectRequest {
+    pub fn new<S: Into<String>>(expression: S, input: SelectInput) -> Self {
+        SelectRequest {
+            expression: expression.into(),
+            input,
+            gzip: false,
+            output_record_delimiter: "\n".to_owned(),
+            output_field_delimiter: ",".to_owned(),
+            keep_all_columns: false,
+            output_header: false,
+            skip_partial_data_record: false,
+            max_skipped_records_allowed: None,
+            enable_payload_crc: true,
+        }
+    }
+
+    /// Queries a CSV object with the default dialect.
+    pub fn csv<S: Into<String>>(expression: S) -> Self {
+        Self::new(expression, SelectInput::Csv(CsvInput::default()))
+    }
+
+    pub fn json<S: Into<String>>(expression: S, json_type: JsonType) -> Self {
+        Self::new(expression, SelectInput::Json(JsonInput::new(json_type)))
+    }
+
+    fn to_xml(&self) -> String {
+        let mut xml = format!(
+            "<SelectRequest><Expression>{}</Expression>{}<OutputSerialization>",
+            encode(&self.expression),
+            self.input.to_xml(self.gzip, false)
+        );
+        match self.input {
+            SelectInput::Csv(_) => {
+                xml += &format!(
+                    "<CSV><RecordDelimiter>{}</RecordDelimiter><FieldDelimiter>{}</FieldDelimiter></CSV>",
+                    encode(&self.output_record_delimiter),
+                    encode(&self.output_field_delimiter)
+                );
+                xml += &format!(
+                    "<KeepAllColumns>{}</KeepAllColumns><OutputHeader>{}</OutputHeader>",
+                    self.keep_all_columns, self.output_header
+                );
+            }
+            SelectInput::Json(_) => {
+                xml += &format!(
+                    "<JSON><RecordDelimiter>{}</RecordDelimiter></JSON>",
+                    encode(&self.output_record_delimiter)
+                );
+            }
+        }
+        xml += &format!(
+            "<OutputRawData>false</OutputRawData><EnablePayloadCrc>{}</EnablePayloadCrc></OutputSerialization>",
+            self.enable_payload_crc
+        );
+        xml += &format!(
+            "<Options><SkipPartialDataRecord>{}</SkipPartialDataRecord>",
+            self.skip_partial_data_record
+        );
+        if let Some(max) = self.max_skipped_records_allowed {
+            xml += &format!(
+                "<MaxSkippedRecordsAllowed>{}</MaxSkippedRecordsAllowed>",
+                max
+            );
+        }
+        xml + "</Options></SelectRequest>"
+    }
+}
+
+/// What `create_select_object_meta` found out about an object.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectObjectMeta {
+    /// Number of splits, for `split-range` queries.
+    pub splits: u32,
+    pub rows: u64,
+    /// CSV only.
+    pub columns: Option<u32>,
+    pub scanned_bytes: u64,
+}
+
+/// Records of a select, without their delimiter. Frames are read as the
+/// iterator advances, so large results are never held in memory at once.
+pub struct SelectRecords {
+    resp: Response,
+    delimiter: Vec<u8>,
+    pending: Vec<u8>,
+    scanned_bytes: u64,
+    finished: bool,
+}
+
+impl SelectRecords {
+    /// Bytes of the object scanned so far, as reported by OSS.
+    pub fn scanned_bytes(&self) -> u64 {
+        self.scanned_bytes
+    }
+
+    /// Reads frames until a whole record is pending or the stream ends.
+    fn fill(&mut self) -> Result<(), Error> {
+        while !self.finished && find(&self.pending, &self.delimiter).is_none() {
+            let frame = Frame::read(&mut self.resp)?;
+            match frame.frame_type {
+                DATA_FRAME => self.pending.extend_from_slice(frame.data()),
+                CONTINUOUS_FRAME => self.scanned_bytes = frame.offset(),
+                END_FRAME => {
+                    self.scanned_bytes = frame.end()?;
+                    self.finished = true;
+                }
+                frame_type => {
+                    return Err(select_error(format!("unexpected frame {:#x}", frame_type)))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for SelectRecords {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(e) = self.fill() {
+            self.finished = true;
+            self.pending.clear();
+            return Some(Err(e));
+        }
+        match find(&self.pending, &self.delimiter) {
+            Some(idx) => {
+                let rest = self.pending.split_off(idx + self.delimiter.len());
+                let mut record = std::mem::replace(&mut self.pending, rest);
+                record.truncate(idx);
+                Some(Ok(record))
+            }
+            None if self.pending.is_empty() => None,
+            None => Some(Ok(std::mem::take(&mut self.pending))),
+        }
+    }
+}
+
+pub trait SelectAPI {
+    /// Runs `request` against the object, see `SelectRecords`.
+    fn select_object<S>(
+        &self,
+        object_name: S,
+        request: &SelectRequest,
+    ) -> Result<SelectRecords, Error>
+    where
+        S: AsRef<str>;
+    /// Scans the object to count its rows and splits, stored by OSS for
+    /// later `split-range` selects. With `overwrite` an existing meta is
+    /// rebuilt.
+    fn create_select_object_meta<S>(
+        &self,
+        object_name: S,
+        input: &SelectInput,
+        overwrite: bool,
+    ) -> Result<SelectObjectMeta, Error>
+    where
+        S: AsRef<str>;
+}
+
+impl<'a> SelectAPI for OSS<'a> {
+    fn select_object<S>(
+        &self,
+        object_name: S,
+        request: &SelectRequest,
+    ) -> Result<SelectRecords, Error>
+    where
+        S: AsRef<str>,
+    {
+        let process = format!("x-oss-process={}/select", request.input.format());
+        let resp = self.send_select(object_name.as_ref(), &process, request.to_xml())?;
+        Ok(SelectRecords {
+            resp,
+            delimiter: request.output_record_delimiter.clone().into_bytes(),
+            pending: vec![],
+            scanned_bytes: 0,
+            finished: false,
+        })
+    }
+
+    fn create_select_object_meta<S>(
+        &self,
+        object_name: S,
+        input: &SelectInput,
+        overwrite: bool,
+    ) -> Result<SelectObjectMeta, Error>
+    where
+        S: AsRef<str>,
+    {
+        let root = match input {
+            SelectInput::Csv(_) => "CsvMetaRequest",
+            SelectInput::Json(_) => "JsonMetaRequest",
+        };
+        let body = format!(
+            "<{root}>{}<OverwriteIfExists>{}</OverwriteIfExists></{root}>",
+            input.to_xml(false, true),
+            overwrite,
+            root = root
+        );
+        let process = format!("x-oss-process={}/meta", input.format());
+        let mut resp = self.send_select(object_name.as_ref(), &process, body)?;
+        loop {
+            let frame = Frame::read(&mut resp)?;
+            match frame.frame_type {
+                CONTINUOUS_FRAME => (),
+                CSV_META_END_FRAME | JSON_META_END_FRAME => return frame.meta_end(),
+                frame_type => {
+                    return Err(select_error(format!("unexpected frame {:#x}", frame_type)))
+                }
+            }
+        }
+    }
+}
+
+impl<'a> OSS<'a> {
+    fn send_select(
+        &self,
+        object_name: &str,
+        process: &str,
+        body: String,
+    ) -> Result<Response, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::POST,
+            bucket: self.bucket(),
+            object: object_name,
+            params: &encode_query(process),
+            resources: process,
+            headers,
+            body: Some(body.into_bytes()),
+        })?;
+        if resp.status().is_success() {
+            Ok(resp)
+        } else {
+            Err(select_error(format!(
+                "can not select object, status code: {}",
+                resp.status()
+            )))
+        }
+    }
+}
+
+struct Frame {
+    frame_type: u32,
+    payload: Vec<u8>,
+}
+
+impl Frame {
+    fn read<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let mut header = [0u8; 12];
+        reader.read_exact(&mut header)?;
+        let frame_type = u32::from_be_bytes([0, header[1], header[2], header[3]]);
+        let len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let mut payload = vec![0; len];
+        reader.read_exact(&mut payload)?;
+        let mut checksum = [0u8; 4];
+        reader.read_exact(&mut checksum)?;
+        let frame = Frame {
+            frame_type,
+            payload,
+        };
+        if frame.payload.len() < 8 {
+            return Err(select_error(format!("frame {:#x} too short", frame_type)));
+        }
+        // only data frames are checksummed, 0 means disabled
+        let checksum = u32::from_be_bytes(checksum);
+        if frame_type == DATA_FRAME && checksum != 0 && crc32(frame.data()) != checksum {
+            return Err(select_error("data frame crc32 mismatch".to_owned()));
+        }
+        Ok(frame)
+    }
+
+    /// Offset in the object the frame was produced at.
+    fn offset(&self) -> u64 {
+        be_u64(&self.payload[..8])
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.payload[8..]
+    }
+
+    /// Checks the status of end frames, their error message starts at
+    /// `message_at`.
+    fn status(&self, message_at: usize) -> Result<(), Error> {
+        let status = be_u32(&self.payload[16..20]);
+        if (200..300).contains(&status) {
+            return Ok(());
+        }
+        let message = String::from_utf8_lossy(&self.payload[message_at..]);
+        Err(select_error(format!("status {}: {}", status, message)))
+    }
+
+    /// Scanned bytes of a successful end frame.
+    fn end(&self) -> Result<u64, Error> {
+        self.check_len(20)?;
+        self.status(20)?;
+        Ok(be_u64(&self.payload[8..16]))
+    }
+
+    fn meta_end(&self) -> Result<SelectObjectMeta, Error> {
+        let csv = self.frame_type == CSV_META_END_FRAME;
+        let len = if csv { 36 } else { 32 };
+        self.check_len(len)?;
+        self.status(len)?;
+        Ok(SelectObjectMeta {
+            scanned_bytes: be_u64(&self.payload[8..16]),
+            splits: be_u32(&self.payload[20..24]),
+            rows: be_u64(&self.payload[24..32]),
+            columns: if csv {
+                Some(be_u32(&self.payload[32..36]))
+            } else {
+                None
+            },
+        })
+    }
+
+    fn check_len(&self, len: usize) -> Result<(), Error> {
+        if self.payload.len() < len {
+            return Err(select_error(format!(
+                "frame {:#x} too short",
+                self.frame_type
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn be_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(bytes);
+    u64::from_be_bytes(buf)
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(bytes);
+    u32::from_be_bytes(buf)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return None;
+    }
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn select_error(msg: String) -> Error {
+    Error::Object(ObjectError::SelectError { msg })
+}
+
+const CRC32_TABLE: [u32; 256] = make_crc32_table();
+
+const fn make_crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32/IEEE, as used for frame payloads.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc = CRC32_TABLE[((crc ^ u32::from(*byte)) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
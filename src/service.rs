@@ -1,10 +1,11 @@
 use quick_xml::{events::Event, Reader};
-use reqwest::header::{HeaderMap, DATE};
+use reqwest::header::HeaderMap;
+use reqwest::Method;
 use std::collections::HashMap;
 
-use super::auth::*;
 use super::errors::Error;
-use super::oss::OSS;
+use super::oss::{SignedRequest, OSS};
+use super::utils::encode_query;
 
 #[derive(Clone, Debug)]
 pub struct ListBuckets {
@@ -148,23 +149,15 @@ impl<'a> ServiceAPI for OSS<'a> {
         } else {
             String::new()
         };
-        let host = self.endpoint();
-        let date = self.date();
-
-        let mut headers = HeaderMap::new();
-        headers.insert(DATE, date.parse()?);
-        let authorization = self.oss_sign(
-            "GET",
-            self.key_id(),
-            self.key_secret(),
-            "",
-            "",
-            &resources_str,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
-
-        let resp = self.client.get(host).headers(headers).send()?;
+        let resp = self.send_signed(SignedRequest {
+            verb: Method::GET,
+            bucket: "",
+            object: "",
+            params: &encode_query(&resources_str),
+            resources: &resources_str,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
 
         let xml_str = resp.text()?;
         let mut result = Vec::new();
@@ -249,7 +242,7 @@ impl<'a> ServiceAPI for OSS<'a> {
                     );
                     break;
                 } // exits the loop when reaching end of file
-                Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+                Err(e) => return Err(e.into()),
                 _ => (), // There are several other `Event`s we do not consider here
             }
             buf.clear();
@@ -0,0 +1,117 @@
+//! Server-side encryption settings, used on object requests and as the
+//! bucket default encryption rule.
+
+use reqwest::header::HeaderMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use super::errors::Error;
+
+const OSS_SSE: &str = "x-oss-server-side-encryption";
+const OSS_SSE_KEY_ID: &str = "x-oss-server-side-encryption-key-id";
+const OSS_SSE_DATA: &str = "x-oss-server-side-data-encryption";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SseAlgorithm {
+    Aes256,
+    Kms,
+    Sm4,
+    /// An algorithm this crate doesn't know, as sent by OSS.
+    Other(String),
+}
+
+impl fmt::Display for SseAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SseAlgorithm::Aes256 => write!(f, "AES256"),
+            SseAlgorithm::Kms => write!(f, "KMS"),
+            SseAlgorithm::Sm4 => write!(f, "SM4"),
+            SseAlgorithm::Other(algorithm) => write!(f, "{}", algorithm),
+        }
+    }
+}
+
+impl From<&str> for SseAlgorithm {
+    fn from(s: &str) -> Self {
+        match s {
+            "AES256" => SseAlgorithm::Aes256,
+            "KMS" => SseAlgorithm::Kms,
+            "SM4" => SseAlgorithm::Sm4,
+            _ => SseAlgorithm::Other(s.to_owned()),
+        }
+    }
+}
+
+impl FromStr for SseAlgorithm {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(SseAlgorithm::from(s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerSideEncryption {
+    pub algorithm: SseAlgorithm,
+    /// KMS only, the customer master key to use instead of the default one.
+    pub kms_key_id: Option<String>,
+    /// KMS only, `Sm4` to encrypt the data with SM4 instead of AES256.
+    pub data_encryption: Option<SseAlgorithm>,
+}
+
+impl ServerSideEncryption {
+    pub fn aes256() -> Self {
+        Self::new(SseAlgorithm::Aes256)
+    }
+
+    pub fn sm4() -> Self {
+        Self::new(SseAlgorithm::Sm4)
+    }
+
+    pub fn kms<S>(kms_key_id: S) -> Self
+    where
+        S: Into<Option<String>>,
+    {
+        ServerSideEncryption {
+            kms_key_id: kms_key_id.into(),
+            ..Self::new(SseAlgorithm::Kms)
+        }
+    }
+
+    fn new(algorithm: SseAlgorithm) -> Self {
+        ServerSideEncryption {
+            algorithm,
+            kms_key_id: None,
+            data_encryption: None,
+        }
+    }
+
+    /// Adds the `x-oss-server-side-*` request headers.
+    pub fn to_headers(&self, headers: &mut HeaderMap) -> Result<(), Error> {
+        headers.insert(OSS_SSE, self.algorithm.to_string().parse()?);
+        if let Some(key_id) = &self.kms_key_id {
+            headers.insert(OSS_SSE_KEY_ID, key_id.parse()?);
+        }
+        if let Some(data_encryption) = &self.data_encryption {
+            headers.insert(OSS_SSE_DATA, data_encryption.to_string().parse()?);
+        }
+        Ok(())
+    }
+
+    /// Reads the encryption of an object from response headers, `None` if it
+    /// is not encrypted server-side. Values it doesn't know are kept as
+    /// they are rather than failing the response.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name| {
+            headers
+                .get(name)
+                .map(|val| String::from_utf8_lossy(val.as_bytes()).into_owned())
+        };
+        Some(ServerSideEncryption {
+            algorithm: SseAlgorithm::from(header(OSS_SSE)?.as_str()),
+            kms_key_id: header(OSS_SSE_KEY_ID),
+            data_encryption: header(OSS_SSE_DATA).map(|val| SseAlgorithm::from(val.as_str())),
+        })
+    }
+}
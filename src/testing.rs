@@ -0,0 +1,1703 @@
+//! An in-process stand-in for OSS, so `ObjectAPI` can be exercised without
+//! network access. Enabled with the `testing` feature.
+//!
+//! The server speaks plain HTTP/1.1 on a local port and is reached through a
+//! proxy-configured client, so the usual `{bucket}.{endpoint}` urls work
+//! unchanged; path-style urls are understood as well. Requests are
+//! authenticated with the same V1 signature code the client uses.
+
+use chrono::{DateTime, Utc};
+use crypto::digest::Digest;
+use crypto::md5::Md5;
+use num_bigint::BigUint;
+use quick_xml::{events::Event, Reader};
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, DATE};
+use reqwest::Proxy;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use super::auth::Auth;
+use super::callback::{self, RsaPublicKey, OSS_CALLBACK, OSS_CALLBACK_VAR, OSS_PUB_KEY_URL};
+use super::crc64;
+use super::errors::Error;
+use super::oss::OSS;
+use super::select::{self, CONTINUOUS_FRAME, DATA_FRAME, END_FRAME};
+use super::select::{CSV_META_END_FRAME, JSON_META_END_FRAME};
+use super::throttle::{MAX_TRAFFIC_LIMIT, MIN_TRAFFIC_LIMIT, OSS_TRAFFIC_LIMIT};
+use super::utils::*;
+
+/// Host name the mock answers for, buckets live at `{bucket}.oss-mock.local`.
+pub const MOCK_HOST: &str = "oss-mock.local";
+
+const OSS_SSE_PREFIX: &str = "x-oss-server-side-";
+
+/// Key the mock signs upload callbacks with, pass it to
+/// `CallbackVerifier::with_public_key`.
+pub const CALLBACK_PUBLIC_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQCgfzW9v8sBgXUrJg4+9+Uw15CZ
+YFN9giyQp7oUsIMYDS7ZBI8fzkoVLn7U1EiR9ch2PZTzIkpaww1xOTJ9wGPqJ2J2
+JbyAHJR3qIGiW+iL64UKQnFrk530d71xQ3dcgDMXVKRK0rQFv1fkEO2zh/YuuUCY
+onxQc7kb3DWvOMoDewIDAQAB
+-----END PUBLIC KEY-----
+";
+
+/// Private half of `CALLBACK_PUBLIC_KEY`, in hex.
+const CALLBACK_PRIVATE_EXPONENT: &str = "3e43bbc4a6708a5fffe5c278ca62ffae6822b7ee0fd5e43787d31a34c699c52df01bcf51fb43dba7c606fc6872d2f56854bee55741d958c6cd233cce62cccdb5bf52ed7aa38f1b2f4d104cf91b26836b629258aa1a0da9120a39af0cb816c7b606111d574915c725c6f474475b081f90aaaaa885594b382f1f4ac13f87f90889";
+
+const CALLBACK_PUB_KEY_URL: &str = "https://gosspublic.alicdn.com/callback_pub_key_v1.pem";
+
+#[derive(Debug, Clone)]
+struct MockObject {
+    content: Vec<u8>,
+    /// `Content-Type`, `x-oss-meta-*` and server-side encryption headers
+    /// given on upload.
+    headers: Vec<(String, String)>,
+    e_tag: String,
+    last_modified: DateTime<Utc>,
+    symlink_target: Option<String>,
+}
+
+#[derive(Debug)]
+struct MockUpload {
+    bucket: String,
+    key: String,
+    headers: Vec<(String, String)>,
+    parts: BTreeMap<u32, (String, Vec<u8>)>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    buckets: BTreeMap<String, BTreeMap<String, MockObject>>,
+    uploads: HashMap<String, MockUpload>,
+    next_upload_id: u64,
+    /// Raw `ServerSideEncryptionRule` documents per bucket.
+    bucket_encryption: HashMap<String, Vec<u8>>,
+    /// Raw `WebsiteConfiguration` documents per bucket.
+    bucket_website: HashMap<String, Vec<u8>>,
+    /// Raw `BucketLoggingStatus` documents per bucket.
+    bucket_logging: HashMap<String, Vec<u8>>,
+    /// Raw `RefererConfiguration` documents per bucket.
+    bucket_referer: HashMap<String, Vec<u8>>,
+    /// LiveChannels per bucket, with their configuration fields as parsed
+    /// by `parse_xml_paths`.
+    live_channels: BTreeMap<String, BTreeMap<String, MockChannel>>,
+}
+
+#[derive(Debug)]
+struct MockChannel {
+    config: HashMap<String, String>,
+    last_modified: DateTime<Utc>,
+}
+
+struct Request {
+    method: String,
+    bucket: String,
+    key: String,
+    query: HashMap<String, Option<String>>,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn has_param(&self, name: &str) -> bool {
+        self.query.contains_key(name)
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.query.get(name).and_then(|v| v.as_deref())
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+}
+
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn new(status: u16) -> Self {
+        Response {
+            status,
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    fn header<S: Into<String>>(mut self, name: &str, value: S) -> Self {
+        self.headers.push((name.to_owned(), value.into()));
+        self
+    }
+
+    fn xml(mut self, body: String) -> Self {
+        self.headers
+            .push(("Content-Type".to_owned(), "application/xml".to_owned()));
+        self.body = body.into_bytes();
+        self
+    }
+
+    fn error(status: u16, code: &str, message: &str) -> Self {
+        Response::new(status).xml(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Error><Code>{}</Code><Message>{}</Message><RequestId>mock</RequestId></Error>",
+            code,
+            escape_xml(message)
+        ))
+    }
+}
+
+/// A running mock server. It shuts down when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    key_id: String,
+    key_secret: String,
+    state: Arc<Mutex<State>>,
+    running: Arc<AtomicBool>,
+}
+
+impl MockServer {
+    /// Binds a free local port and accepts requests signed with
+    /// `key_id`/`key_secret`.
+    pub fn start<S>(key_id: S, key_secret: S) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let server = MockServer {
+            addr: listener.local_addr()?,
+            key_id: key_id.into(),
+            key_secret: key_secret.into(),
+            state: Arc::new(Mutex::new(State::default())),
+            running: Arc::new(AtomicBool::new(true)),
+        };
+
+        let handler = Handler {
+            signer: OSS::new(
+                server.key_id.clone(),
+                server.key_secret.clone(),
+                MOCK_HOST.to_owned(),
+                String::new(),
+            ),
+            state: server.state.clone(),
+        };
+        let running = server.running.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if !running.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let handler = handler.clone();
+                    thread::spawn(move || handler.serve(stream));
+                }
+            }
+        });
+        Ok(server)
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Endpoint to hand to `OSS::new` together with `client()`.
+    pub fn endpoint(&self) -> String {
+        format!("http://{}", MOCK_HOST)
+    }
+
+    /// A client that routes every request to this server.
+    pub fn client(&self) -> Client {
+        Client::builder()
+            .proxy(Proxy::all(&format!("http://{}", self.addr)).expect("valid proxy url"))
+            .build()
+            .expect("failed to build mock client")
+    }
+
+    /// An `OSS` instance for `bucket`, wired to this server.
+    pub fn oss(&self, bucket: &str) -> OSS<'static> {
+        let mut oss = OSS::new(
+            self.key_id.clone(),
+            self.key_secret.clone(),
+            self.endpoint(),
+            bucket.to_owned(),
+        );
+        oss.client = self.client();
+        oss
+    }
+
+    /// Stores an object directly, bypassing the HTTP layer.
+    pub fn insert_object<S1, S2>(&self, bucket: S1, key: S2, content: Vec<u8>)
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        let object = MockObject::new(content, vec![]);
+        self.state
+            .lock()
+            .unwrap()
+            .buckets
+            .entry(bucket.into())
+            .or_default()
+            .insert(key.into(), object);
+    }
+
+    /// Content of a stored object, for assertions.
+    pub fn object(&self, bucket: &str, key: &str) -> Option<Vec<u8>> {
+        self.state
+            .lock()
+            .unwrap()
+            .buckets
+            .get(bucket)
+            .and_then(|objects| objects.get(key))
+            .map(|object| object.content.clone())
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        // wake up the accept loop so it notices
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+impl MockObject {
+    fn new(content: Vec<u8>, headers: Vec<(String, String)>) -> Self {
+        let mut md5 = Md5::new();
+        md5.input(&content);
+        MockObject {
+            e_tag: format!("\"{}\"", md5.result_str().to_uppercase()),
+            content,
+            headers,
+            last_modified: now(),
+            symlink_target: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Handler {
+    signer: OSS<'static>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Handler {
+    fn serve(&self, stream: TcpStream) {
+        let mut reader = BufReader::new(match stream.try_clone() {
+            Ok(stream) => stream,
+            Err(_) => return,
+        });
+        let (resp, head) = match read_request(&mut reader) {
+            Ok(Some(req)) => {
+                debug!("mock oss: {} /{}/{}", req.method, req.bucket, req.key);
+                let head = req.method == "HEAD";
+                (self.handle(req), head)
+            }
+            Ok(None) => return,
+            Err(msg) => (Response::error(400, "InvalidArgument", &msg), false),
+        };
+        let _ = write_response(stream, resp, head);
+    }
+
+    fn handle(&self, mut req: Request) -> Response {
+        if let Err(resp) = self.authenticate(&req) {
+            return resp;
+        }
+        for param in &["OSSAccessKeyId", "Expires", "Signature"] {
+            req.query.remove(*param);
+        }
+        let traffic_limit = match req.query.remove(OSS_TRAFFIC_LIMIT) {
+            Some(limit) => limit,
+            None => req.header(OSS_TRAFFIC_LIMIT).map(str::to_owned),
+        };
+        if let Some(limit) = traffic_limit {
+            match limit.parse::<u64>() {
+                Ok(limit) if (MIN_TRAFFIC_LIMIT..=MAX_TRAFFIC_LIMIT).contains(&limit) => (),
+                _ => {
+                    return Response::error(
+                        400,
+                        "InvalidArgument",
+                        "x-oss-traffic-limit is out of range",
+                    )
+                }
+            }
+        }
+        let mut state = self.state.lock().unwrap();
+        if req.bucket.is_empty() {
+            return match req.method.as_str() {
+                "GET" => list_buckets(&state),
+                _ => Response::error(501, "NotImplemented", "not supported by the mock"),
+            };
+        }
+        if req.key.is_empty() {
+            return match req.method.as_str() {
+                "GET" if req.param("list-type") == Some("2") => list_objects_v2(&state, &req),
+                "GET" if req.has_param("encryption") => get_bucket_encryption(&state, &req),
+                "GET" if req.has_param("live") => list_live_channels(&state, &req),
+                "GET" if req.has_param("website") => get_bucket_website(&state, &req),
+                "GET" if req.has_param("logging") => {
+                    let logging = state.bucket_logging.get(&req.bucket);
+                    Response::new(200).xml(logging.map_or_else(
+                        || "<BucketLoggingStatus/>".to_string(),
+                        |logging| String::from_utf8_lossy(logging).into_owned(),
+                    ))
+                }
+                "GET" if req.has_param("referer") => {
+                    let referer = state.bucket_referer.get(&req.bucket);
+                    Response::new(200).xml(referer.map_or_else(
+                        || "<RefererConfiguration><AllowEmptyReferer>true</AllowEmptyReferer><RefererList/></RefererConfiguration>".to_string(),
+                        |referer| String::from_utf8_lossy(referer).into_owned(),
+                    ))
+                }
+                "PUT" if req.has_param("encryption") => {
+                    state.bucket_encryption.insert(req.bucket, req.body);
+                    Response::new(200)
+                }
+                "DELETE" if req.has_param("encryption") => {
+                    state.bucket_encryption.remove(&req.bucket);
+                    Response::new(204)
+                }
+                "PUT" if req.has_param("website") => {
+                    state.bucket_website.insert(req.bucket, req.body);
+                    Response::new(200)
+                }
+                "DELETE" if req.has_param("website") => {
+                    state.bucket_website.remove(&req.bucket);
+                    Response::new(204)
+                }
+                "PUT" if req.has_param("logging") => {
+                    state.bucket_logging.insert(req.bucket, req.body);
+                    Response::new(200)
+                }
+                "DELETE" if req.has_param("logging") => {
+                    state.bucket_logging.remove(&req.bucket);
+                    Response::new(204)
+                }
+                "PUT" if req.has_param("referer") => {
+                    state.bucket_referer.insert(req.bucket, req.body);
+                    Response::new(200)
+                }
+                _ => Response::error(501, "NotImplemented", "not supported by the mock"),
+            };
+        }
+        let callback = req.header(OSS_CALLBACK).map(|callback| {
+            let vars = req.header(OSS_CALLBACK_VAR).map(str::to_owned);
+            (
+                callback.to_owned(),
+                vars,
+                req.bucket.clone(),
+                req.key.clone(),
+            )
+        });
+        let resp = match req.method.as_str() {
+            "POST" if req.has_param("uploads") => init_upload(&mut state, req),
+            "PUT" if req.has_param("uploadId") => upload_part(&mut state, req),
+            "POST" if req.has_param("uploadId") => complete_upload(&mut state, req),
+            "DELETE" if req.has_param("uploadId") => abort_upload(&mut state, req),
+            "PUT" if req.has_param("symlink") => put_symlink(&mut state, req),
+            "GET" if req.has_param("symlink") => get_symlink(&state, req),
+            "PUT" if req.header("x-oss-copy-source").is_some() => copy_object(&mut state, req),
+            "PUT" | "GET" | "DELETE" if req.has_param("live") => live_channel(&mut state, req),
+            "POST" | "GET" if req.has_param("vod") => vod_playlist(&mut state, req),
+            // images are returned and saved as they are, the mock does not
+            // process them
+            "GET" if req.has_param("x-oss-process") => get_object(&state, req),
+            "POST" if select_format(&req, "select").is_some() => select_object(&state, req),
+            "POST" if select_format(&req, "meta").is_some() => select_meta(&state, req),
+            "POST" if req.has_param("x-oss-process") => save_processed(&mut state, req),
+            "PUT" if req.query.is_empty() => put_object(&mut state, req),
+            "GET" | "HEAD" if req.query.is_empty() => get_object(&state, req),
+            "DELETE" if req.query.is_empty() => delete_object(&mut state, req),
+            _ => Response::error(501, "NotImplemented", "not supported by the mock"),
+        };
+        match callback {
+            Some((callback, vars, bucket, key)) if resp.status == 200 => {
+                let stored = state
+                    .buckets
+                    .get(&bucket)
+                    .and_then(|objects| objects.get(&key));
+                let object = match stored {
+                    Some(object) => object.clone(),
+                    None => return no_such_key(),
+                };
+                drop(state);
+                run_callback(resp, &callback, vars.as_deref(), &bucket, &key, &object)
+            }
+            _ => resp,
+        }
+    }
+
+    /// Checks either the `Authorization` header or the presigned url params.
+    fn authenticate(&self, req: &Request) -> Result<(), Response> {
+        let mut headers = req.headers.clone();
+        let provided = if let Some(signature) = req.param("Signature") {
+            let expires = req.param("Expires").unwrap_or_default();
+            if expires.parse::<i64>().unwrap_or(0) < now().timestamp() {
+                return Err(Response::error(403, "AccessDenied", "Request has expired."));
+            }
+            headers.insert(DATE, expires.parse().map_err(|_| bad_signature())?);
+            format!(
+                "OSS {}:{}",
+                req.param("OSSAccessKeyId").unwrap_or_default(),
+                signature
+            )
+        } else {
+            match req.header(AUTHORIZATION.as_str()) {
+                Some(auth) => auth.to_owned(),
+                None => {
+                    return Err(Response::error(
+                        403,
+                        "AccessDenied",
+                        "Authorization header is missing.",
+                    ))
+                }
+            }
+        };
+        let resources = OSS::get_params_str(&req.query);
+        let expected = self.signer.oss_sign(
+            &req.method,
+            self.signer.key_id(),
+            self.signer.key_secret(),
+            &req.bucket,
+            &req.key,
+            &resources,
+            &headers,
+        );
+        if provided == expected {
+            Ok(())
+        } else {
+            Err(bad_signature())
+        }
+    }
+}
+
+fn bad_signature() -> Response {
+    Response::error(
+        403,
+        "SignatureDoesNotMatch",
+        "The request signature we calculated does not match the signature you provided.",
+    )
+}
+
+fn now() -> DateTime<Utc> {
+    // OSS keeps second precision
+    DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap_or_else(Utc::now)
+}
+
+fn get_bucket_encryption(state: &State, req: &Request) -> Response {
+    match state.bucket_encryption.get(&req.bucket) {
+        Some(rule) => Response::new(200).xml(String::from_utf8_lossy(rule).into_owned()),
+        None => Response::error(
+            404,
+            "NoSuchServerSideEncryptionRule",
+            "The server side encryption configuration was not found.",
+        ),
+    }
+}
+
+fn get_bucket_website(state: &State, req: &Request) -> Response {
+    match state.bucket_website.get(&req.bucket) {
+        Some(config) => Response::new(200).xml(String::from_utf8_lossy(config).into_owned()),
+        None => Response::error(
+            404,
+            "NoSuchWebsiteConfiguration",
+            "The specified bucket does not have a website configuration.",
+        ),
+    }
+}
+
+fn no_such_key() -> Response {
+    Response::error(404, "NoSuchKey", "The specified key does not exist.")
+}
+
+fn list_buckets(state: &State) -> Response {
+    let mut body = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ListAllMyBucketsResult><Owner><ID>mock</ID><DisplayName>mock</DisplayName></Owner><Buckets>");
+    for name in state.buckets.keys() {
+        body += &format!(
+            "<Bucket><Name>{}</Name><CreationDate>2020-01-01T00:00:00.000Z</CreationDate><Location>oss-mock</Location><ExtranetEndpoint>{}</ExtranetEndpoint><IntranetEndpoint>{}</IntranetEndpoint><StorageClass>Standard</StorageClass></Bucket>",
+            escape_xml(name), MOCK_HOST, MOCK_HOST
+        );
+    }
+    body += "</Buckets></ListAllMyBucketsResult>";
+    Response::new(200).xml(body)
+}
+
+fn no_such_live_channel() -> Response {
+    Response::error(
+        404,
+        "NoSuchLiveChannel",
+        "The specified live channel does not exist.",
+    )
+}
+
+fn live_channel_urls(bucket: &str, name: &str, channel: &MockChannel) -> String {
+    let playlist = channel
+        .config
+        .get("Target/PlaylistName")
+        .map_or("playlist.m3u8", String::as_str);
+    format!(
+        "<PublishUrls><Url>rtmp://{}.{}/live/{}</Url></PublishUrls><PlayUrls><Url>http://{}.{}/{}/{}</Url></PlayUrls>",
+        bucket,
+        MOCK_HOST,
+        escape_xml(name),
+        bucket,
+        MOCK_HOST,
+        escape_xml(name),
+        escape_xml(playlist)
+    )
+}
+
+/// Create, status, info, stat, history and delete of `?live`. Channels are
+/// never pushed to, so they stay `Idle` without history.
+fn live_channel(state: &mut State, req: Request) -> Response {
+    let channels = state.live_channels.entry(req.bucket.clone()).or_default();
+    if req.method == "PUT" && !req.has_param("status") {
+        let config = match parse_xml_paths(&req.body) {
+            Some(config) => config,
+            None => return Response::error(400, "MalformedXML", "invalid live channel"),
+        };
+        let channel = MockChannel {
+            config,
+            last_modified: now(),
+        };
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<CreateLiveChannelResult>{}</CreateLiveChannelResult>",
+            live_channel_urls(&req.bucket, &req.key, &channel)
+        );
+        channels.insert(req.key, channel);
+        return Response::new(200).xml(body);
+    }
+    let channel = match channels.get_mut(&req.key) {
+        Some(channel) => channel,
+        None => return no_such_live_channel(),
+    };
+    match (req.method.as_str(), req.param("comp")) {
+        ("PUT", _) => match req.param("status") {
+            Some(status @ "enabled") | Some(status @ "disabled") => {
+                channel.config.insert("Status".to_owned(), status.to_owned());
+                channel.last_modified = now();
+                Response::new(200)
+            }
+            _ => Response::error(400, "InvalidArgument", "invalid live channel status"),
+        },
+        ("DELETE", _) => {
+            channels.remove(&req.key);
+            Response::new(204)
+        }
+        ("GET", Some("stat")) => Response::new(200).xml(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<LiveChannelStat><Status>Idle</Status></LiveChannelStat>".to_owned(),
+        ),
+        ("GET", Some("history")) => Response::new(200).xml(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<LiveChannelHistory></LiveChannelHistory>".to_owned(),
+        ),
+        ("GET", None) => {
+            let field = |name: &str| escape_xml(channel.config.get(name).map_or("", String::as_str));
+            Response::new(200).xml(format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<LiveChannelConfiguration><Description>{}</Description><Status>{}</Status><Target><Type>HLS</Type><FragDuration>{}</FragDuration><FragCount>{}</FragCount><PlaylistName>{}</PlaylistName></Target></LiveChannelConfiguration>",
+                field("Description"),
+                field("Status"),
+                field("Target/FragDuration"),
+                field("Target/FragCount"),
+                field("Target/PlaylistName"),
+            ))
+        }
+        _ => Response::error(501, "NotImplemented", "not supported by the mock"),
+    }
+}
+
+fn list_live_channels(state: &State, req: &Request) -> Response {
+    let prefix = req.param("prefix").unwrap_or_default();
+    let marker = req.param("marker").unwrap_or_default();
+    let max_keys: usize = match req.param("max-keys").map(str::parse).transpose() {
+        Ok(max_keys) => max_keys.unwrap_or(100),
+        Err(_) => return Response::error(400, "InvalidArgument", "invalid max-keys"),
+    };
+    let empty = BTreeMap::new();
+    let channels = state.live_channels.get(&req.bucket).unwrap_or(&empty);
+    let mut matching = channels
+        .iter()
+        .filter(|(name, _)| name.starts_with(prefix) && name.as_str() > marker);
+    let mut body = String::new();
+    let mut last = None;
+    for (name, channel) in matching.by_ref().take(max_keys) {
+        body += &format!(
+            "<LiveChannel><Name>{}</Name><Description>{}</Description><Status>{}</Status><LastModified>{}</LastModified>{}</LiveChannel>",
+            escape_xml(name),
+            escape_xml(channel.config.get("Description").map_or("", String::as_str)),
+            channel.config.get("Status").map_or("enabled", String::as_str),
+            channel.last_modified.format("%Y-%m-%dT%H:%M:%S.000Z"),
+            live_channel_urls(&req.bucket, name, channel)
+        );
+        last = Some(name);
+    }
+    let truncated = matching.next().is_some();
+    let next_marker = match last {
+        Some(last) if truncated => escape_xml(last),
+        _ => String::new(),
+    };
+    Response::new(200).xml(format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ListLiveChannelResult><Prefix>{}</Prefix><Marker>{}</Marker><MaxKeys>{}</MaxKeys><IsTruncated>{}</IsTruncated><NextMarker>{}</NextMarker>{}</ListLiveChannelResult>",
+        escape_xml(prefix),
+        escape_xml(marker),
+        max_keys,
+        truncated,
+        next_marker,
+        body
+    ))
+}
+
+/// `POST {channel}/{playlist}?vod` stores an empty VOD playlist, `GET
+/// {channel}?vod` returns it.
+fn vod_playlist(state: &mut State, req: Request) -> Response {
+    let time = |name| req.param(name).and_then(|t| t.parse::<i64>().ok());
+    match (time("startTime"), time("endTime")) {
+        (Some(start), Some(end)) if start < end => (),
+        _ => return Response::error(400, "InvalidArgument", "invalid startTime or endTime"),
+    }
+    let (name, playlist) = match req.method.as_str() {
+        "POST" => match req.key.split_once('/') {
+            Some((name, playlist)) if playlist.ends_with(".m3u8") => (name, Some(playlist)),
+            _ => return Response::error(400, "InvalidArgument", "invalid playlist name"),
+        },
+        _ => (req.key.as_str(), None),
+    };
+    let channel = match state
+        .live_channels
+        .get(&req.bucket)
+        .and_then(|c| c.get(name))
+    {
+        Some(channel) => channel,
+        None => return no_such_live_channel(),
+    };
+    let duration = channel
+        .config
+        .get("Target/FragDuration")
+        .map_or("5", String::as_str);
+    let m3u8 = format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:{}\n#EXT-X-ENDLIST\n",
+        duration
+    );
+    match playlist {
+        Some(_) => {
+            let headers = vec![(
+                "Content-Type".to_owned(),
+                "application/x-mpegURL".to_owned(),
+            )];
+            let object = MockObject::new(m3u8.into_bytes(), headers);
+            state
+                .buckets
+                .entry(req.bucket.clone())
+                .or_default()
+                .insert(req.key.clone(), object);
+            Response::new(200)
+        }
+        None => {
+            let mut resp = Response::new(200).header("Content-Type", "application/x-mpegURL");
+            resp.body = m3u8.into_bytes();
+            resp
+        }
+    }
+}
+
+fn list_objects_v2(state: &State, req: &Request) -> Response {
+    let prefix = req.param("prefix").unwrap_or_default();
+    let delimiter = req.param("delimiter").unwrap_or_default();
+    let token = req.param("continuation-token").unwrap_or_default();
+    let max_keys: usize = match req.param("max-keys").map(str::parse).transpose() {
+        Ok(max_keys) => max_keys.unwrap_or(100),
+        Err(_) => return Response::error(400, "InvalidArgument", "invalid max-keys"),
+    };
+
+    let mut contents = vec![];
+    let mut prefixes: Vec<String> = vec![];
+    let mut next_token = None;
+    let empty = BTreeMap::new();
+    let objects = state.buckets.get(&req.bucket).unwrap_or(&empty);
+    for (key, object) in objects
+        .iter()
+        .filter(|(key, _)| key.starts_with(prefix) && key.as_str() > token)
+    {
+        let common_prefix = if delimiter.is_empty() {
+            None
+        } else {
+            key[prefix.len()..]
+                .find(delimiter)
+                .map(|idx| key[..prefix.len() + idx + delimiter.len()].to_owned())
+        };
+        if let Some(common_prefix) = &common_prefix {
+            if prefixes.last() == Some(common_prefix) {
+                continue;
+            }
+        }
+        if contents.len() + prefixes.len() == max_keys {
+            next_token = Some(key.clone());
+            break;
+        }
+        match common_prefix {
+            Some(common_prefix) => prefixes.push(common_prefix),
+            None => contents.push((key, object)),
+        }
+    }
+
+    let mut body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ListBucketResult><Name>{}</Name><Prefix>{}</Prefix><MaxKeys>{}</MaxKeys><Delimiter>{}</Delimiter><IsTruncated>{}</IsTruncated><KeyCount>{}</KeyCount>",
+        escape_xml(&req.bucket),
+        escape_xml(prefix),
+        max_keys,
+        escape_xml(delimiter),
+        next_token.is_some(),
+        contents.len() + prefixes.len()
+    );
+    if let Some(token) = next_token {
+        // the last returned key, listing continues after it
+        let last = contents
+            .last()
+            .map(|(key, _)| key.as_str())
+            .into_iter()
+            .chain(prefixes.last().map(String::as_str))
+            .max()
+            .unwrap_or(token.as_str());
+        body += &format!(
+            "<NextContinuationToken>{}</NextContinuationToken>",
+            escape_xml(last)
+        );
+    }
+    for (key, object) in contents {
+        body += &format!(
+            "<Contents><Key>{}</Key><LastModified>{}</LastModified><ETag>{}</ETag><Type>Normal</Type><Size>{}</Size><StorageClass>Standard</StorageClass></Contents>",
+            escape_xml(key),
+            object.last_modified.format("%Y-%m-%dT%H:%M:%S.000Z"),
+            escape_xml(&object.e_tag),
+            object.content.len()
+        );
+    }
+    for prefix in prefixes {
+        body += &format!(
+            "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+            escape_xml(&prefix)
+        );
+    }
+    body += "</ListBucketResult>";
+    Response::new(200).xml(body)
+}
+
+/// `Content-Type` and `x-oss-meta-*` headers of a request, as stored with
+/// the object.
+fn object_headers(req: &Request) -> Vec<(String, String)> {
+    req.headers
+        .iter()
+        .filter(|(name, _)| {
+            name.as_str() == "content-type"
+                || name.as_str().starts_with(OSS_META_PREFIX)
+                || name.as_str().starts_with(OSS_SSE_PREFIX)
+        })
+        .filter_map(|(name, val)| Some((name.as_str().to_owned(), val.to_str().ok()?.to_owned())))
+        .collect()
+}
+
+fn put_object(state: &mut State, req: Request) -> Response {
+    if let Some(content_md5) = req.header("content-md5") {
+        let mut md5 = Md5::new();
+        md5.input(&req.body);
+        let mut digest = [0u8; 16];
+        md5.result(&mut digest);
+        if content_md5 != base64::encode(digest) {
+            return Response::error(
+                400,
+                "InvalidDigest",
+                "The Content-MD5 you specified was invalid.",
+            );
+        }
+    }
+    let headers = object_headers(&req);
+    let object = MockObject::new(req.body, headers);
+    let e_tag = object.e_tag.clone();
+    let crc = crc64::checksum(&object.content);
+    state
+        .buckets
+        .entry(req.bucket)
+        .or_default()
+        .insert(req.key, object);
+    Response::new(200)
+        .header("ETag", e_tag)
+        .header("x-oss-hash-crc64ecma", crc.to_string())
+}
+
+/// Value of the string field `name` of a flat JSON object.
+fn json_field(json: &str, name: &str) -> Option<String> {
+    let start = json.find(&format!("\"{}\":", name))? + name.len() + 3;
+    json_strings(&json[start..]).into_iter().next()
+}
+
+/// The string literals of a JSON document, in order.
+fn json_strings(json: &str) -> Vec<String> {
+    let mut strings = vec![];
+    let mut chars = json.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+        let mut string = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('u') => {
+                        let code = chars.by_ref().take(4).collect::<String>();
+                        let c = u32::from_str_radix(&code, 16)
+                            .ok()
+                            .and_then(std::char::from_u32);
+                        string.extend(c);
+                    }
+                    Some(c) => string.push(c),
+                    None => break,
+                },
+                c => string.push(c),
+            }
+        }
+        strings.push(string);
+    }
+    strings
+}
+
+/// Posts the callback of a successful upload, signed with the mock's key,
+/// and answers with the callback server's response like OSS does.
+fn run_callback(
+    resp: Response,
+    callback: &str,
+    vars: Option<&str>,
+    bucket: &str,
+    key: &str,
+    object: &MockObject,
+) -> Response {
+    let decode = |val: &str| {
+        base64::decode(val)
+            .ok()
+            .map(|val| String::from_utf8_lossy(&val).into_owned())
+    };
+    let callback = match decode(callback) {
+        Some(callback) => callback,
+        None => return Response::error(400, "InvalidArgument", "invalid x-oss-callback"),
+    };
+    let (url, mut body) = match (
+        json_field(&callback, "callbackUrl"),
+        json_field(&callback, "callbackBody"),
+    ) {
+        (Some(url), Some(body)) => (url, body),
+        _ => return Response::error(400, "InvalidArgument", "invalid x-oss-callback"),
+    };
+    let body_type = json_field(&callback, "callbackBodyType")
+        .unwrap_or_else(|| "application/x-www-form-urlencoded".to_owned());
+    let mime_type = object
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .map_or("", |(_, value)| value.as_str());
+    let mut substitutions = vec![
+        ("bucket".to_owned(), bucket.to_owned()),
+        ("object".to_owned(), key.to_owned()),
+        ("etag".to_owned(), object.e_tag.trim_matches('"').to_owned()),
+        ("size".to_owned(), object.content.len().to_string()),
+        ("mimeType".to_owned(), mime_type.to_owned()),
+    ];
+    let vars = vars
+        .and_then(decode)
+        .map(|v| json_strings(&v))
+        .unwrap_or_default();
+    for pair in vars.chunks(2) {
+        if let [name, value] = pair {
+            substitutions.push((name.clone(), value.clone()));
+        }
+    }
+    for (name, value) in substitutions {
+        body = body.replace(&format!("${{{}}}", name), &value);
+    }
+
+    let url = url.split(';').next().unwrap_or_default().to_owned();
+    let path_and_query = url
+        .split_once("://")
+        .and_then(|(_, rest)| rest.find('/').map(|idx| rest[idx..].to_owned()))
+        .unwrap_or_else(|| "/".to_owned());
+    let mut content = match path_and_query.split_once('?') {
+        Some((path, query)) => format!("{}?{}", percent_decode(path), query),
+        None => percent_decode(&path_and_query),
+    };
+    content += "\n";
+    content += &body;
+    let key = RsaPublicKey::from_pem(CALLBACK_PUBLIC_KEY).expect("valid mock key");
+    let d = BigUint::parse_bytes(CALLBACK_PRIVATE_EXPONENT.as_bytes(), 16).expect("valid mock key");
+    let encoded = callback::md5_encoded(content.as_bytes(), key.size());
+    let signature = BigUint::from_bytes_be(&encoded)
+        .modpow(&d, &key.n)
+        .to_bytes_be();
+    let mut padded = vec![0; key.size() - signature.len()];
+    padded.extend(signature);
+
+    let mut request = Client::builder()
+        .no_proxy()
+        .build()
+        .expect("failed to build callback client")
+        .post(&url)
+        .header("Content-Type", body_type)
+        .header(OSS_PUB_KEY_URL, base64::encode(CALLBACK_PUB_KEY_URL))
+        .header(AUTHORIZATION, base64::encode(&padded))
+        .body(body);
+    if let Some(host) = json_field(&callback, "callbackHost") {
+        request = request.header("Host", host);
+    }
+    let headers = resp
+        .headers
+        .into_iter()
+        .filter(|(name, _)| !name.eq_ignore_ascii_case("content-type"));
+    match request.send() {
+        Ok(callback_resp) if callback_resp.status().is_success() => {
+            let content_type = callback_resp
+                .headers()
+                .get("content-type")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("application/json")
+                .to_owned();
+            let mut resp = Response::new(200);
+            resp.headers = headers.collect();
+            resp.headers.push(("Content-Type".to_owned(), content_type));
+            resp.body = callback_resp
+                .bytes()
+                .map(|b| b.to_vec())
+                .unwrap_or_default();
+            resp
+        }
+        result => {
+            let msg = match result {
+                Ok(callback_resp) => format!("callback returned {}", callback_resp.status()),
+                Err(e) => format!("callback failed: {}", e),
+            };
+            let mut resp = Response::error(203, "CallbackFailed", &msg);
+            resp.headers.extend(headers);
+            resp
+        }
+    }
+}
+
+/// Handles `x-oss-process=...|sys/saveas,o_{object},b_{bucket}` bodies.
+fn save_processed(state: &mut State, req: Request) -> Response {
+    let body = String::from_utf8_lossy(&req.body).into_owned();
+    let save_as = match body.split_once("|sys/saveas,") {
+        Some((_, save_as)) => save_as,
+        None => return Response::error(400, "InvalidArgument", "sys/saveas is missing"),
+    };
+    let (mut key, mut bucket) = (None, req.bucket.clone());
+    for param in save_as.split(',') {
+        let decoded = |val| {
+            base64::decode_config(val, base64::URL_SAFE_NO_PAD)
+                .ok()
+                .and_then(|val| String::from_utf8(val).ok())
+        };
+        match param.split_at(param.find('_').map_or(0, |idx| idx + 1)) {
+            ("o_", val) => key = decoded(val),
+            ("b_", val) => bucket = decoded(val).unwrap_or_default(),
+            _ => (),
+        }
+    }
+    let key = match key {
+        Some(key) if !bucket.is_empty() => key,
+        _ => return Response::error(400, "InvalidArgument", "invalid sys/saveas"),
+    };
+    let object = match state.buckets.get(&req.bucket).and_then(|o| o.get(&req.key)) {
+        Some(object) => MockObject::new(object.content.clone(), object.headers.clone()),
+        None => return no_such_key(),
+    };
+    let size = object.content.len();
+    state
+        .buckets
+        .entry(bucket.clone())
+        .or_default()
+        .insert(key.clone(), object);
+    let body = format!(
+        "{{\"bucket\": \"{}\", \"fileSize\": {}, \"object\": \"{}\", \"status\": \"OK\"}}",
+        bucket, size, key
+    );
+    let mut resp = Response::new(200).header("Content-Type", "application/json");
+    resp.body = body.into_bytes();
+    resp
+}
+
+/// `csv` or `json` for `x-oss-process={format}/{action}` requests.
+fn select_format<'r>(req: &'r Request, action: &str) -> Option<&'r str> {
+    let (format, act) = req.param("x-oss-process")?.split_once('/')?;
+    match format {
+        "csv" | "json" if act == action => Some(format),
+        _ => None,
+    }
+}
+
+/// Text of the leaf elements of `body`, keyed by their path below the root
+/// such as `InputSerialization/CSV/RecordDelimiter`.
+fn parse_xml_paths(body: &[u8]) -> Option<HashMap<String, String>> {
+    let xml_str = std::str::from_utf8(body).ok()?;
+    let mut reader = Reader::from_str(xml_str);
+    let mut buf = Vec::new();
+    let mut path: Vec<String> = vec![];
+    let mut values = HashMap::new();
+    reader.trim_text(true);
+    loop {
+        match reader.read_event(&mut buf).ok()? {
+            Event::Start(ref e) => path.push(String::from_utf8_lossy(e.name()).into_owned()),
+            Event::End(_) => {
+                path.pop();
+            }
+            Event::Text(ref e) if path.len() > 1 => {
+                let text = e.unescape_and_decode(&reader).ok()?;
+                values.insert(path[1..].join("/"), text);
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+    Some(values)
+}
+
+fn select_frame(frame_type: u32, payload: &[u8], checksum: u32) -> Vec<u8> {
+    let mut frame = vec![1];
+    frame.extend_from_slice(&frame_type.to_be_bytes()[1..]);
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&0u32.to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame.extend_from_slice(&checksum.to_be_bytes());
+    frame
+}
+
+/// Rows of the object, split and filtered as the input serialization says.
+/// The header line, when the object has one, is returned separately.
+fn select_rows(
+    content: &[u8],
+    format: &str,
+    params: &HashMap<String, String>,
+) -> Option<(Option<String>, Vec<String>)> {
+    let decoded = |name: &str, default: &str| match params.get(name) {
+        Some(val) => base64::decode(val)
+            .ok()
+            .and_then(|val| String::from_utf8(val).ok()),
+        None => Some(default.to_owned()),
+    };
+    let content = String::from_utf8_lossy(content);
+    if format == "json" {
+        let rows = match params
+            .get("InputSerialization/JSON/Type")
+            .map(|t| t.as_str())
+        {
+            Some("LINES") => content.lines().map(str::to_owned).collect(),
+            _ => vec![content.trim().to_owned()],
+        };
+        return Some((None, rows.into_iter().filter(|r| !r.is_empty()).collect()));
+    }
+    let delimiter = decoded("InputSerialization/CSV/RecordDelimiter", "\n")?;
+    let comment = decoded("InputSerialization/CSV/CommentCharacter", "#")?;
+    let mut rows = content
+        .split(delimiter.as_str())
+        .filter(|row| !row.is_empty() && !row.starts_with(comment.as_str()))
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    let header = match params
+        .get("InputSerialization/CSV/FileHeaderInfo")
+        .map(|h| h.to_ascii_uppercase())
+        .as_deref()
+    {
+        Some("USE") | Some("IGNORE") if !rows.is_empty() => Some(rows.remove(0)),
+        _ => None,
+    };
+    Some((header, rows))
+}
+
+/// Runs `select * from ossobject [limit n]`, the only query the mock
+/// understands, answering with small data frames to exercise reassembly.
+fn select_object(state: &State, req: Request) -> Response {
+    let format = select_format(&req, "select").unwrap_or_default().to_owned();
+    let params = match parse_xml_paths(&req.body) {
+        Some(params) => params,
+        None => return Response::error(400, "MalformedXML", "invalid select request"),
+    };
+    let expression = params
+        .get("Expression")
+        .and_then(|e| base64::decode(e).ok())
+        .map(|e| String::from_utf8_lossy(&e).to_ascii_lowercase())
+        .unwrap_or_default();
+    let words = expression.split_whitespace().collect::<Vec<_>>();
+    let limit = match words.as_slice() {
+        ["select", "*", "from", "ossobject"] => None,
+        ["select", "*", "from", "ossobject", "limit", n] => match n.parse::<usize>() {
+            Ok(n) => Some(n),
+            Err(_) => return Response::error(400, "InvalidArgument", "invalid limit"),
+        },
+        _ => return Response::error(400, "InvalidArgument", "unsupported expression"),
+    };
+    let content = match state.buckets.get(&req.bucket).and_then(|o| o.get(&req.key)) {
+        Some(object) => object.content.clone(),
+        None => return no_such_key(),
+    };
+    let (header, rows) = match select_rows(&content, &format, &params) {
+        Some(rows) => rows,
+        None => return Response::error(400, "InvalidArgument", "invalid input serialization"),
+    };
+    let output = |name: &str, default: &str| {
+        params
+            .get(name)
+            .and_then(|val| base64::decode(val).ok())
+            .map_or_else(
+                || default.to_owned(),
+                |val| String::from_utf8_lossy(&val).into_owned(),
+            )
+    };
+    let section = if format == "csv" { "CSV" } else { "JSON" };
+    let record_delimiter = output(
+        &format!("OutputSerialization/{}/RecordDelimiter", section),
+        "\n",
+    );
+    let input_field = output("InputSerialization/CSV/FieldDelimiter", ",");
+    let output_field = output("OutputSerialization/CSV/FieldDelimiter", ",");
+    let output_header = params
+        .get("OutputSerialization/OutputHeader")
+        .map(|h| h.as_str())
+        == Some("true");
+    let crc = params
+        .get("OutputSerialization/EnablePayloadCrc")
+        .map(|c| c.as_str())
+        != Some("false");
+
+    let mut data = String::new();
+    let header = header.filter(|_| output_header);
+    for row in header
+        .iter()
+        .chain(rows.iter().take(limit.unwrap_or(usize::MAX)))
+    {
+        if format == "csv" {
+            data += &row
+                .split(input_field.as_str())
+                .collect::<Vec<_>>()
+                .join(&output_field);
+        } else {
+            data += row;
+        }
+        data += &record_delimiter;
+    }
+
+    let mut body = select_frame(CONTINUOUS_FRAME, &0u64.to_be_bytes(), 0);
+    for (idx, chunk) in data.as_bytes().chunks(7).enumerate() {
+        let mut payload = ((idx * 7) as u64).to_be_bytes().to_vec();
+        payload.extend_from_slice(chunk);
+        let checksum = if crc { select::crc32(chunk) } else { 0 };
+        body.extend(select_frame(DATA_FRAME, &payload, checksum));
+    }
+    let mut payload = (content.len() as u64).to_be_bytes().to_vec();
+    payload.extend_from_slice(&(content.len() as u64).to_be_bytes());
+    payload.extend_from_slice(&200u32.to_be_bytes());
+    body.extend(select_frame(END_FRAME, &payload, 0));
+
+    let mut resp = Response::new(206).header("Content-Type", "application/octet-stream");
+    resp.body = body;
+    resp
+}
+
+/// Counts rows and columns, the whole object being one split.
+fn select_meta(state: &State, req: Request) -> Response {
+    let format = select_format(&req, "meta").unwrap_or_default().to_owned();
+    let params = match parse_xml_paths(&req.body) {
+        Some(params) => params,
+        None => return Response::error(400, "MalformedXML", "invalid meta request"),
+    };
+    let content = match state.buckets.get(&req.bucket).and_then(|o| o.get(&req.key)) {
+        Some(object) => object.content.clone(),
+        None => return no_such_key(),
+    };
+    let (_, rows) = match select_rows(&content, &format, &params) {
+        Some(rows) => rows,
+        None => return Response::error(400, "InvalidArgument", "invalid input serialization"),
+    };
+    let mut payload = (content.len() as u64).to_be_bytes().to_vec();
+    payload.extend_from_slice(&(content.len() as u64).to_be_bytes());
+    payload.extend_from_slice(&200u32.to_be_bytes());
+    payload.extend_from_slice(&1u32.to_be_bytes());
+    payload.extend_from_slice(&(rows.len() as u64).to_be_bytes());
+    let frame_type = if format == "csv" {
+        let field = params
+            .get("InputSerialization/CSV/FieldDelimiter")
+            .and_then(|val| base64::decode(val).ok())
+            .map_or_else(
+                || ",".to_owned(),
+                |val| String::from_utf8_lossy(&val).into_owned(),
+            );
+        let columns = rows
+            .first()
+            .map_or(0, |row| row.split(field.as_str()).count());
+        payload.extend_from_slice(&(columns as u32).to_be_bytes());
+        CSV_META_END_FRAME
+    } else {
+        JSON_META_END_FRAME
+    };
+
+    let mut body = select_frame(CONTINUOUS_FRAME, &0u64.to_be_bytes(), 0);
+    body.extend(select_frame(frame_type, &payload, 0));
+    let mut resp = Response::new(200).header("Content-Type", "application/octet-stream");
+    resp.body = body;
+    resp
+}
+
+fn copy_object(state: &mut State, req: Request) -> Response {
+    let source = req.header("x-oss-copy-source").unwrap_or_default();
+    let source = percent_decode(source.split('?').next().unwrap_or_default());
+    let (bucket, key) = match source.trim_start_matches('/').split_once('/') {
+        Some(source) => source,
+        None => return Response::error(400, "InvalidArgument", "invalid x-oss-copy-source"),
+    };
+    let mut object = match state.buckets.get(bucket).and_then(|o| o.get(key)) {
+        Some(object) => object.clone(),
+        None => return no_such_key(),
+    };
+    if req.header("x-oss-metadata-directive") == Some("REPLACE") {
+        object.headers = object_headers(&req);
+    } else if req.header("x-oss-server-side-encryption").is_some() {
+        object
+            .headers
+            .retain(|(name, _)| !name.starts_with(OSS_SSE_PREFIX));
+        object.headers.extend(
+            object_headers(&req)
+                .into_iter()
+                .filter(|(name, _)| name.starts_with(OSS_SSE_PREFIX)),
+        );
+    }
+    object.last_modified = now();
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<CopyObjectResult><ETag>{}</ETag><LastModified>{}</LastModified></CopyObjectResult>",
+        escape_xml(&object.e_tag),
+        object.last_modified.format("%Y-%m-%dT%H:%M:%S.000Z")
+    );
+    state
+        .buckets
+        .entry(req.bucket)
+        .or_default()
+        .insert(req.key, object);
+    Response::new(200).xml(body)
+}
+
+fn put_symlink(state: &mut State, req: Request) -> Response {
+    let target = match req.header("x-oss-symlink-target") {
+        Some(target) => percent_decode(target),
+        None => return Response::error(400, "InvalidArgument", "x-oss-symlink-target missing"),
+    };
+    let mut object = MockObject::new(vec![], object_headers(&req));
+    object.symlink_target = Some(target);
+    state
+        .buckets
+        .entry(req.bucket)
+        .or_default()
+        .insert(req.key, object);
+    Response::new(200)
+}
+
+fn get_symlink(state: &State, req: Request) -> Response {
+    match state
+        .buckets
+        .get(&req.bucket)
+        .and_then(|objects| objects.get(&req.key))
+        .and_then(|object| object.symlink_target.as_ref())
+    {
+        Some(target) => Response::new(200).header("x-oss-symlink-target", uri_encode(target, true)),
+        None => no_such_key(),
+    }
+}
+
+fn get_object(state: &State, req: Request) -> Response {
+    let objects = match state.buckets.get(&req.bucket) {
+        Some(objects) => objects,
+        None => {
+            return Response::error(404, "NoSuchBucket", "The specified bucket does not exist.")
+        }
+    };
+    let mut object = match objects.get(&req.key) {
+        Some(object) => object,
+        None => return no_such_key(),
+    };
+    if let Some(target) = &object.symlink_target {
+        object = match objects.get(target) {
+            Some(object) => object,
+            None => return no_such_key(),
+        };
+    }
+
+    let last_modified = gmt_date(&object.last_modified);
+    let parse_date = |name: &str| {
+        req.header(name)
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+    };
+    if let Some(e_tag) = req.header("if-match") {
+        if e_tag != object.e_tag {
+            return Response::error(
+                412,
+                "PreconditionFailed",
+                "At least one of the pre-conditions you specified did not hold.",
+            );
+        }
+    }
+    if let Some(since) = parse_date("if-unmodified-since") {
+        if object.last_modified > since {
+            return Response::error(
+                412,
+                "PreconditionFailed",
+                "At least one of the pre-conditions you specified did not hold.",
+            );
+        }
+    }
+    let not_modified = match req.header("if-none-match") {
+        Some(e_tag) => e_tag == object.e_tag,
+        None => parse_date("if-modified-since").is_some_and(|since| object.last_modified <= since),
+    };
+    if not_modified {
+        return Response::new(304)
+            .header("ETag", object.e_tag.as_str())
+            .header("Last-Modified", last_modified);
+    }
+
+    let size = object.content.len() as u64;
+    let range = req
+        .header("range")
+        .and_then(|range| parse_range(range, size));
+    let mut resp = match range {
+        Some((start, end)) => {
+            let mut resp = Response::new(206)
+                .header("Content-Range", format!("bytes {}-{}/{}", start, end, size));
+            resp.body = object.content[start as usize..=end as usize].to_vec();
+            resp
+        }
+        None => {
+            let mut resp = Response::new(200);
+            resp.body = object.content.clone();
+            resp
+        }
+    };
+    resp = resp
+        .header("ETag", object.e_tag.as_str())
+        .header("Last-Modified", last_modified)
+        .header("Accept-Ranges", "bytes")
+        .header(
+            "x-oss-hash-crc64ecma",
+            crc64::checksum(&object.content).to_string(),
+        );
+    for (name, val) in object.headers.iter() {
+        resp = resp.header(name, val.as_str());
+    }
+    resp
+}
+
+/// Resolves a `Range` header against `size`, `None` when the whole object
+/// should be returned, as OSS does for invalid ranges.
+fn parse_range(range: &str, size: u64) -> Option<(u64, u64)> {
+    let (start, end) = range.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end) = match (start.parse::<u64>().ok(), end.parse::<u64>().ok()) {
+        (Some(start), Some(end)) => (start, end.min(size.checked_sub(1)?)),
+        (Some(start), None) if end.is_empty() => (start, size.checked_sub(1)?),
+        (None, Some(n)) if start.is_empty() => {
+            (size.checked_sub(n.min(size))?, size.checked_sub(1)?)
+        }
+        _ => return None,
+    };
+    if start <= end {
+        Some((start, end))
+    } else {
+        None
+    }
+}
+
+fn delete_object(state: &mut State, req: Request) -> Response {
+    if let Some(objects) = state.buckets.get_mut(&req.bucket) {
+        objects.remove(&req.key);
+    }
+    Response::new(204)
+}
+
+fn init_upload(state: &mut State, req: Request) -> Response {
+    state.next_upload_id += 1;
+    let upload_id = format!("MOCKUPLOAD{:016X}", state.next_upload_id);
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<InitiateMultipartUploadResult><Bucket>{}</Bucket><Key>{}</Key><UploadId>{}</UploadId></InitiateMultipartUploadResult>",
+        escape_xml(&req.bucket),
+        escape_xml(&req.key),
+        upload_id
+    );
+    let upload = MockUpload {
+        headers: object_headers(&req),
+        bucket: req.bucket,
+        key: req.key,
+        parts: BTreeMap::new(),
+    };
+    state.uploads.insert(upload_id, upload);
+    Response::new(200).xml(body)
+}
+
+fn find_upload<'s>(state: &'s mut State, req: &Request) -> Option<&'s mut MockUpload> {
+    state
+        .uploads
+        .get_mut(req.param("uploadId")?)
+        .filter(|upload| upload.bucket == req.bucket && upload.key == req.key)
+}
+
+fn no_such_upload() -> Response {
+    Response::error(404, "NoSuchUpload", "The specified upload does not exist.")
+}
+
+fn upload_part(state: &mut State, req: Request) -> Response {
+    let part_number = match req.param("partNumber").and_then(|n| n.parse::<u32>().ok()) {
+        Some(n) if (1..=10000).contains(&n) => n,
+        _ => return Response::error(400, "InvalidArgument", "invalid partNumber"),
+    };
+    let upload = match find_upload(state, &req) {
+        Some(upload) => upload,
+        None => return no_such_upload(),
+    };
+    let part = MockObject::new(req.body, vec![]);
+    let e_tag = part.e_tag;
+    let crc = crc64::checksum(&part.content);
+    upload
+        .parts
+        .insert(part_number, (e_tag.clone(), part.content));
+    Response::new(200)
+        .header("ETag", e_tag)
+        .header("x-oss-hash-crc64ecma", crc.to_string())
+}
+
+fn complete_upload(state: &mut State, req: Request) -> Response {
+    let requested = match parse_complete_parts(&req.body) {
+        Some(parts) => parts,
+        None => {
+            return Response::error(400, "MalformedXML", "invalid CompleteMultipartUpload body")
+        }
+    };
+    let upload = match find_upload(state, &req) {
+        Some(upload) => upload,
+        None => return no_such_upload(),
+    };
+    let mut content = vec![];
+    let mut last = 0;
+    for (part_number, e_tag) in requested.iter() {
+        match upload.parts.get(part_number) {
+            Some((stored, data)) if stored == e_tag && *part_number > last => {
+                content.extend_from_slice(data)
+            }
+            _ => {
+                return Response::error(400, "InvalidPart", "One or more of the specified parts could not be found or the specified entity tag might not have matched the part's entity tag.")
+            }
+        }
+        last = *part_number;
+    }
+    let upload_id = req.param("uploadId").unwrap_or_default().to_owned();
+    let upload = match state.uploads.remove(&upload_id) {
+        Some(upload) => upload,
+        None => return no_such_upload(),
+    };
+    let mut object = MockObject::new(content, upload.headers);
+    object.e_tag = format!("\"{}-{}\"", object.e_tag.trim_matches('"'), requested.len());
+    let crc = crc64::checksum(&object.content);
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<CompleteMultipartUploadResult><Location>http://{}.{}/{}</Location><Bucket>{}</Bucket><Key>{}</Key><ETag>{}</ETag></CompleteMultipartUploadResult>",
+        escape_xml(&req.bucket),
+        MOCK_HOST,
+        escape_xml(&req.key),
+        escape_xml(&req.bucket),
+        escape_xml(&req.key),
+        escape_xml(&object.e_tag)
+    );
+    state
+        .buckets
+        .entry(req.bucket)
+        .or_default()
+        .insert(req.key, object);
+    Response::new(200)
+        .header("x-oss-hash-crc64ecma", crc.to_string())
+        .xml(body)
+}
+
+fn parse_complete_parts(body: &[u8]) -> Option<Vec<(u32, String)>> {
+    let xml_str = std::str::from_utf8(body).ok()?;
+    let mut reader = Reader::from_str(xml_str);
+    let mut buf = Vec::new();
+    let mut parts = vec![];
+    let mut part_number = 0;
+    let mut e_tag = String::new();
+    reader.trim_text(true);
+    loop {
+        match reader.read_event(&mut buf).ok()? {
+            Event::Start(ref e) => match e.name() {
+                b"PartNumber" => {
+                    part_number = reader
+                        .read_text(e.name(), &mut Vec::new())
+                        .ok()?
+                        .parse()
+                        .ok()?
+                }
+                b"ETag" => e_tag = reader.read_text(e.name(), &mut Vec::new()).ok()?,
+                _ => (),
+            },
+            Event::End(ref e) if e.name() == b"Part" => {
+                parts.push((part_number, std::mem::take(&mut e_tag)))
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+    Some(parts)
+}
+
+fn abort_upload(state: &mut State, req: Request) -> Response {
+    if find_upload(state, &req).is_none() {
+        return no_such_upload();
+    }
+    state
+        .uploads
+        .remove(req.param("uploadId").unwrap_or_default());
+    Response::new(204)
+}
+
+/// Reads one request, `None` when the peer closed the connection.
+fn read_request<R: BufRead>(reader: &mut R) -> Result<Option<Request>, String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_owned(), target.to_owned()),
+        _ => return Err(format!("invalid request line: {}", line.trim())),
+    };
+
+    let mut headers = HeaderMap::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, val) = header
+            .split_once(':')
+            .ok_or_else(|| format!("invalid header: {}", header))?;
+        headers.append(
+            HeaderName::from_bytes(name.trim().as_bytes()).map_err(|e| e.to_string())?,
+            HeaderValue::from_str(val.trim()).map_err(|e| e.to_string())?,
+        );
+    }
+
+    let body = if headers
+        .get("transfer-encoding")
+        .is_some_and(|te| te.as_bytes().eq_ignore_ascii_case(b"chunked"))
+    {
+        read_chunked(reader)?
+    } else {
+        let len = headers
+            .get("content-length")
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.parse().ok())
+            .unwrap_or(0);
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+        body
+    };
+
+    // proxied requests carry the absolute url, direct ones only the path
+    let (host, path) = match target.strip_prefix("http://") {
+        Some(rest) => match rest.find('/') {
+            Some(idx) => (rest[..idx].to_owned(), rest[idx..].to_owned()),
+            None => (rest.to_owned(), "/".to_owned()),
+        },
+        None => (
+            headers
+                .get("host")
+                .and_then(|h| h.to_str().ok())
+                .unwrap_or_default()
+                .to_owned(),
+            target,
+        ),
+    };
+    let (path, query_str) = path.split_once('?').unwrap_or((path.as_str(), ""));
+    let path = path.trim_start_matches('/');
+    // anything but `{bucket}.oss-mock.local` is addressed path-style
+    let (bucket, path) = match host
+        .rsplit_once(':')
+        .map_or(host.as_str(), |(host, _)| host)
+        .strip_suffix(MOCK_HOST)
+        .and_then(|bucket| bucket.strip_suffix('.'))
+    {
+        Some(bucket) => (bucket, path),
+        None => path.split_once('/').unwrap_or((path, "")),
+    };
+    let mut query = HashMap::new();
+    for pair in query_str.split('&').filter(|p| !p.is_empty()) {
+        match pair.split_once('=') {
+            Some((k, v)) => query.insert(percent_decode(k), Some(percent_decode(v))),
+            None => query.insert(percent_decode(pair), None),
+        };
+    }
+
+    Ok(Some(Request {
+        method,
+        bucket: percent_decode(bucket),
+        key: percent_decode(path),
+        query,
+        headers,
+        body,
+    }))
+}
+
+fn read_chunked<R: BufRead>(reader: &mut R) -> Result<Vec<u8>, String> {
+    let mut body = vec![];
+    let mut line = String::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        let size = usize::from_str_radix(line.trim().split(';').next().unwrap_or("0"), 16)
+            .map_err(|e| e.to_string())?;
+        let mut chunk = vec![0; size + 2];
+        if size == 0 {
+            // trailer, then the final CRLF
+            loop {
+                line.clear();
+                reader.read_line(&mut line).map_err(|e| e.to_string())?;
+                if line.trim_end().is_empty() {
+                    return Ok(body);
+                }
+            }
+        }
+        reader.read_exact(&mut chunk).map_err(|e| e.to_string())?;
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
+
+fn write_response(mut stream: TcpStream, resp: Response, head: bool) -> std::io::Result<()> {
+    let reason = reqwest::StatusCode::from_u16(resp.status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("Unknown");
+    let mut out = format!("HTTP/1.1 {} {}\r\n", resp.status, reason);
+    out += &format!("Date: {}\r\n", gmt_date(&Utc::now()));
+    out += "Server: AliyunOSS\r\nx-oss-request-id: mock\r\nConnection: close\r\n";
+    for (name, val) in resp.headers.iter() {
+        out += &format!("{}: {}\r\n", name, val);
+    }
+    if resp.status != 304 && resp.status != 204 {
+        out += &format!("Content-Length: {}\r\n", resp.body.len());
+    }
+    out += "\r\n";
+    stream.write_all(out.as_bytes())?;
+    if !head {
+        stream.write_all(&resp.body)?;
+    }
+    stream.flush()
+}
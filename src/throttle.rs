@@ -0,0 +1,136 @@
+//! Bandwidth limits: `x-oss-traffic-limit` asks OSS to throttle a single
+//! request, `RateLimiter` paces all transfers of a client locally.
+
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Request header, and presigned url param, holding a server-side limit in
+/// bit/s.
+pub const OSS_TRAFFIC_LIMIT: &str = "x-oss-traffic-limit";
+/// Smallest `x-oss-traffic-limit` OSS accepts, 30 KB/s.
+pub const MIN_TRAFFIC_LIMIT: u64 = 245_760;
+/// Largest `x-oss-traffic-limit` OSS accepts, 100 MB/s.
+pub const MAX_TRAFFIC_LIMIT: u64 = 838_860_800;
+
+/// Rejects a limit OSS would answer with 400, before anything is sent.
+pub(crate) fn check_traffic_limit(bits_per_sec: u64) -> Result<(), String> {
+    if (MIN_TRAFFIC_LIMIT..=MAX_TRAFFIC_LIMIT).contains(&bits_per_sec) {
+        Ok(())
+    } else {
+        Err(format!(
+            "traffic limit {} is outside {}..={} bit/s",
+            bits_per_sec, MIN_TRAFFIC_LIMIT, MAX_TRAFFIC_LIMIT
+        ))
+    }
+}
+
+/// Token bucket shared by every transfer it is set on, see
+/// `OSS::set_rate_limiter`. Transfers are paced chunk by chunk as data is
+/// sent or arrives.
+#[derive(Debug)]
+pub struct RateLimiter {
+    bytes_per_sec: f64,
+    burst: f64,
+    state: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// Allows `bytes_per_sec` on average, with bursts of up to one second.
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self::with_burst(bytes_per_sec, bytes_per_sec)
+    }
+
+    pub fn with_burst(bytes_per_sec: u64, burst: u64) -> Self {
+        RateLimiter {
+            bytes_per_sec: bytes_per_sec.max(1) as f64,
+            burst: burst.max(1) as f64,
+            state: Mutex::new(Bucket {
+                tokens: burst.max(1) as f64,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes `bytes` tokens, blocking until the bucket has refilled enough.
+    /// Requests larger than the burst go into debt that later callers wait
+    /// out as well.
+    pub fn acquire(&self, bytes: u64) {
+        let wait = {
+            let mut bucket = self.state.lock().unwrap();
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * self.bytes_per_sec).min(self.burst);
+            bucket.refilled_at = now;
+            bucket.tokens -= bytes as f64;
+            if bucket.tokens < 0.0 {
+                Duration::from_secs_f64(-bucket.tokens / self.bytes_per_sec)
+            } else {
+                Duration::from_secs(0)
+            }
+        };
+        if wait > Duration::from_secs(0) {
+            thread::sleep(wait);
+        }
+    }
+}
+
+/// Passes writes through to `inner`, pacing them with `limiter`.
+pub(crate) struct ThrottledWriter<'a, W> {
+    inner: W,
+    limiter: Option<&'a RateLimiter>,
+}
+
+impl<'a, W: Write> ThrottledWriter<'a, W> {
+    pub(crate) fn new(inner: W, limiter: Option<&'a RateLimiter>) -> Self {
+        ThrottledWriter { inner, limiter }
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<'a, W: Write> Write for ThrottledWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if let Some(limiter) = self.limiter {
+            limiter.acquire(n as u64);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Passes reads through from `inner`, pacing them with `limiter`. Owns its
+/// limiter so it can be a request body.
+pub(crate) struct ThrottledReader<R> {
+    inner: R,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl<R: Read> ThrottledReader<R> {
+    pub(crate) fn new(inner: R, limiter: Option<Arc<RateLimiter>>) -> Self {
+        ThrottledReader { inner, limiter }
+    }
+}
+
+impl<R: Read> Read for ThrottledReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire(n as u64);
+        }
+        Ok(n)
+    }
+}
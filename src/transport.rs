@@ -0,0 +1,81 @@
+use reqwest::blocking::{Client, Request};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use super::errors::Error;
+
+/// Sends the signed requests of an `OSS` instance. The default goes straight
+/// to the network, see `cassette` for recording and replaying.
+pub trait Transport: Send + Sync + fmt::Debug {
+    fn send(&self, client: &Client, req: Request) -> Result<Response, Error>;
+}
+
+/// A response whose body is read lazily, either from the network or from
+/// memory.
+pub struct Response {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Box<dyn Read + Send>,
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+impl Response {
+    pub fn new<R>(status: StatusCode, headers: HeaderMap, body: R) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        Response {
+            status,
+            headers,
+            body: Box::new(body),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn bytes(mut self) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![];
+        self.body.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn text(self) -> Result<String, Error> {
+        Ok(String::from_utf8(self.bytes()?)?)
+    }
+
+    pub fn copy_to<W: Write + ?Sized>(&mut self, w: &mut W) -> Result<u64, Error> {
+        Ok(io::copy(&mut self.body, w)?)
+    }
+}
+
+impl Read for Response {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.body.read(buf)
+    }
+}
+
+impl From<reqwest::blocking::Response> for Response {
+    fn from(resp: reqwest::blocking::Response) -> Self {
+        Response {
+            status: resp.status(),
+            headers: resp.headers().to_owned(),
+            body: Box::new(resp),
+        }
+    }
+}
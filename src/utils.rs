@@ -1,4 +1,6 @@
 use super::errors::Error;
+use chrono::{DateTime, Utc};
+use quick_xml::events::BytesText;
 use reqwest::header::{HeaderMap, HeaderName};
 use std::collections::HashMap;
 
@@ -35,3 +37,60 @@ where
     }
     Ok(headers)
 }
+
+pub(crate) fn escape_xml(raw: &str) -> String {
+    String::from_utf8_lossy(BytesText::from_plain_str(raw).escaped()).into_owned()
+}
+
+/// Formats `date` the way HTTP `Date`-like headers expect it.
+pub(crate) fn gmt_date(date: &DateTime<Utc>) -> String {
+    date.format("%a, %d %b %Y %T GMT").to_string()
+}
+
+/// Percent-encodes everything but RFC 3986 unreserved characters, and `/`
+/// when `keep_slash` is set, as object keys in url paths need.
+pub(crate) fn uri_encode(raw: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(raw.len());
+    for byte in raw.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded += &format!("%{:02X}", byte),
+        }
+    }
+    encoded
+}
+
+/// Percent-encodes the keys and values of a canonical `k=v&k` query string,
+/// e.g. the sub-resources that are signed unencoded.
+pub(crate) fn encode_query(canonical: &str) -> String {
+    canonical
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) => format!("{}={}", uri_encode(k, false), uri_encode(v, false)),
+            None => uri_encode(pair, false),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+pub(crate) fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = |b: u8| (b as char).to_digit(16);
+            if let (Some(high), Some(low)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                out.push((high * 16 + low) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
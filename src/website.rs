@@ -0,0 +1,503 @@
+//! Static website hosting configuration of a bucket, see
+//! `BucketAPI::put_bucket_website`.
+
+use quick_xml::{events::Event, Reader};
+use std::fmt;
+use std::str::FromStr;
+
+use super::errors::{BucketError, Error};
+use super::utils::escape_xml;
+
+/// What a request for a "directory" without trailing slash gets when
+/// `support_sub_dir` is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubDirType {
+    /// Redirected to `{dir}/`.
+    Redirect,
+    /// `NoSuchKey`.
+    NotFound,
+    /// `{dir}/{suffix}` is returned.
+    Index,
+}
+
+impl fmt::Display for SubDirType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubDirType::Redirect => write!(f, "0"),
+            SubDirType::NotFound => write!(f, "1"),
+            SubDirType::Index => write!(f, "2"),
+        }
+    }
+}
+
+impl FromStr for SubDirType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(SubDirType::Redirect),
+            "1" => Ok(SubDirType::NotFound),
+            "2" => Ok(SubDirType::Index),
+            _ => Err(website_error(format!("unknown sub-directory type: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexDocument {
+    /// Object served for `/` and, with `support_sub_dir`, for `{dir}/`.
+    pub suffix: String,
+    pub support_sub_dir: bool,
+    pub sub_dir_type: SubDirType,
+}
+
+impl IndexDocument {
+    pub fn new<S: Into<String>>(suffix: S) -> Self {
+        IndexDocument {
+            suffix: suffix.into(),
+            support_sub_dir: false,
+            sub_dir_type: SubDirType::Redirect,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorDocument {
+    /// Object served when a key does not exist.
+    pub key: String,
+    /// Status it is served with, 404 by default.
+    pub http_status: Option<u16>,
+}
+
+/// When a routing rule applies. Every condition given must hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoutingCondition {
+    pub key_prefix_equals: Option<String>,
+    pub key_suffix_equals: Option<String>,
+    pub http_error_code_returned_equals: Option<u16>,
+    /// Request headers, by name, that must have the given value.
+    pub include_headers: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectType {
+    /// Fetch the object from `MirrorConfig::url` (mirror-back).
+    Mirror,
+    /// Redirect the client to another host.
+    External,
+    /// Serve another object of the bucket.
+    Internal,
+    /// Like `External`, for buckets served through Alibaba Cloud CDN.
+    AliCdn,
+}
+
+impl fmt::Display for RedirectType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RedirectType::Mirror => write!(f, "Mirror"),
+            RedirectType::External => write!(f, "External"),
+            RedirectType::Internal => write!(f, "Internal"),
+            RedirectType::AliCdn => write!(f, "AliCDN"),
+        }
+    }
+}
+
+impl FromStr for RedirectType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Mirror" => Ok(RedirectType::Mirror),
+            "External" => Ok(RedirectType::External),
+            "Internal" => Ok(RedirectType::Internal),
+            "AliCDN" => Ok(RedirectType::AliCdn),
+            _ => Err(website_error(format!("unknown redirect type: {}", s))),
+        }
+    }
+}
+
+/// Headers forwarded to the mirror origin.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MirrorHeaders {
+    /// Pass all request headers, except those in `remove`.
+    pub pass_all: bool,
+    pub pass: Vec<String>,
+    pub remove: Vec<String>,
+    /// Headers set to a fixed value.
+    pub set: Vec<(String, String)>,
+}
+
+/// Where and how missing objects are fetched back from, for
+/// `RedirectType::Mirror`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MirrorConfig {
+    pub url: String,
+    pub pass_query_string: Option<bool>,
+    pub follow_redirect: Option<bool>,
+    pub check_md5: Option<bool>,
+    pub headers: Option<MirrorHeaders>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingRedirect {
+    pub redirect_type: RedirectType,
+    pub pass_query_string: Option<bool>,
+    /// Replaces the whole key, `${key}` stands for the original one.
+    pub replace_key_with: Option<String>,
+    /// Replaces `key_prefix_equals`, with `enable_replace_prefix`.
+    pub replace_key_prefix_with: Option<String>,
+    pub enable_replace_prefix: Option<bool>,
+    /// `http` or `https`, `External` and `AliCdn` only.
+    pub protocol: Option<String>,
+    pub host_name: Option<String>,
+    /// 301, 302 or 307, `External` and `AliCdn` only.
+    pub http_redirect_code: Option<u16>,
+    pub mirror: Option<MirrorConfig>,
+}
+
+impl RoutingRedirect {
+    pub fn new(redirect_type: RedirectType) -> Self {
+        RoutingRedirect {
+            redirect_type,
+            pass_query_string: None,
+            replace_key_with: None,
+            replace_key_prefix_with: None,
+            enable_replace_prefix: None,
+            protocol: None,
+            host_name: None,
+            http_redirect_code: None,
+            mirror: None,
+        }
+    }
+
+    /// A `Mirror` redirect fetching from `url`.
+    pub fn mirror<S: Into<String>>(url: S) -> Self {
+        RoutingRedirect {
+            mirror: Some(MirrorConfig {
+                url: url.into(),
+                ..Default::default()
+            }),
+            ..Self::new(RedirectType::Mirror)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingRule {
+    /// Rules are matched in ascending order, the first match applies.
+    pub rule_number: u32,
+    pub condition: RoutingCondition,
+    pub redirect: RoutingRedirect,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebsiteConfiguration {
+    pub index_document: Option<IndexDocument>,
+    pub error_document: Option<ErrorDocument>,
+    pub routing_rules: Vec<RoutingRule>,
+}
+
+impl WebsiteConfiguration {
+    pub(crate) fn to_xml(&self) -> String {
+        let mut xml = String::from("<WebsiteConfiguration>");
+        if let Some(index) = &self.index_document {
+            xml += &format!(
+                "<IndexDocument><Suffix>{}</Suffix><SupportSubDir>{}</SupportSubDir><Type>{}</Type></IndexDocument>",
+                escape_xml(&index.suffix),
+                index.support_sub_dir,
+                index.sub_dir_type
+            );
+        }
+        if let Some(error) = &self.error_document {
+            xml += &format!("<ErrorDocument><Key>{}</Key>", escape_xml(&error.key));
+            xml += &optional("HttpStatus", error.http_status);
+            xml += "</ErrorDocument>";
+        }
+        if !self.routing_rules.is_empty() {
+            xml += "<RoutingRules>";
+            for rule in &self.routing_rules {
+                xml += &rule.to_xml();
+            }
+            xml += "</RoutingRules>";
+        }
+        xml + "</WebsiteConfiguration>"
+    }
+
+    pub(crate) fn from_xml(xml_str: &str) -> Result<Self, Error> {
+        let mut reader = Reader::from_str(xml_str);
+        let mut buf = Vec::new();
+        let mut config = WebsiteConfiguration::default();
+        // containers currently open, leaves are read whole
+        let mut path: Vec<Vec<u8>> = vec![];
+        let mut rule = None;
+        let mut header = (String::new(), String::new());
+        reader.trim_text(true);
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(ref e) => {
+                    let name = e.name().to_vec();
+                    match name.as_slice() {
+                        b"WebsiteConfiguration"
+                        | b"RoutingRules"
+                        | b"Condition"
+                        | b"Redirect"
+                        | b"IncludeHeader"
+                        | b"Set" => path.push(name),
+                        b"IndexDocument" => {
+                            config.index_document = Some(IndexDocument::new(""));
+                            path.push(name)
+                        }
+                        b"ErrorDocument" => {
+                            config.error_document = Some(ErrorDocument {
+                                key: String::new(),
+                                http_status: None,
+                            });
+                            path.push(name)
+                        }
+                        b"RoutingRule" => {
+                            rule = Some(RoutingRule {
+                                rule_number: 0,
+                                condition: RoutingCondition::default(),
+                                redirect: RoutingRedirect::new(RedirectType::Internal),
+                            });
+                            path.push(name)
+                        }
+                        b"MirrorHeaders" => {
+                            mirror(&mut rule).headers = Some(MirrorHeaders::default());
+                            path.push(name)
+                        }
+                        leaf if LEAVES.contains(&leaf) => {
+                            let text = reader.read_text(e.name(), &mut Vec::new())?;
+                            let parent = path.last().map_or(&[][..], Vec::as_slice);
+                            read_leaf(&mut config, &mut rule, &mut header, parent, &name, text)?;
+                        }
+                        // settings this crate doesn't model, such as MirrorAuth
+                        _ => reader.read_to_end(e.name(), &mut Vec::new())?,
+                    }
+                }
+                Event::End(ref e) => {
+                    match e.name() {
+                        b"RoutingRule" => config.routing_rules.extend(rule.take()),
+                        b"IncludeHeader" => {
+                            if let Some(rule) = rule.as_mut() {
+                                rule.condition
+                                    .include_headers
+                                    .push(std::mem::take(&mut header));
+                            }
+                        }
+                        b"Set" => {
+                            let set = std::mem::take(&mut header);
+                            mirror_headers(&mut rule).set.push(set);
+                        }
+                        _ => (),
+                    }
+                    path.pop();
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+        Ok(config)
+    }
+}
+
+impl RoutingRule {
+    fn to_xml(&self) -> String {
+        let condition = &self.condition;
+        let mut xml = format!(
+            "<RoutingRule><RuleNumber>{}</RuleNumber><Condition>",
+            self.rule_number
+        );
+        xml += &optional(
+            "KeyPrefixEquals",
+            condition.key_prefix_equals.as_deref().map(escape_xml),
+        );
+        xml += &optional(
+            "KeySuffixEquals",
+            condition.key_suffix_equals.as_deref().map(escape_xml),
+        );
+        xml += &optional(
+            "HttpErrorCodeReturnedEquals",
+            condition.http_error_code_returned_equals,
+        );
+        for (key, equals) in &condition.include_headers {
+            xml += &format!(
+                "<IncludeHeader><Key>{}</Key><Equals>{}</Equals></IncludeHeader>",
+                escape_xml(key),
+                escape_xml(equals)
+            );
+        }
+
+        let redirect = &self.redirect;
+        xml += &format!(
+            "</Condition><Redirect><RedirectType>{}</RedirectType>",
+            redirect.redirect_type
+        );
+        xml += &optional("PassQueryString", redirect.pass_query_string);
+        if let Some(mirror) = &redirect.mirror {
+            xml += &format!("<MirrorURL>{}</MirrorURL>", escape_xml(&mirror.url));
+            xml += &optional("MirrorPassQueryString", mirror.pass_query_string);
+            xml += &optional("MirrorFollowRedirect", mirror.follow_redirect);
+            xml += &optional("MirrorCheckMd5", mirror.check_md5);
+            if let Some(headers) = &mirror.headers {
+                xml += &format!("<MirrorHeaders><PassAll>{}</PassAll>", headers.pass_all);
+                for pass in &headers.pass {
+                    xml += &format!("<Pass>{}</Pass>", escape_xml(pass));
+                }
+                for remove in &headers.remove {
+                    xml += &format!("<Remove>{}</Remove>", escape_xml(remove));
+                }
+                for (key, value) in &headers.set {
+                    xml += &format!(
+                        "<Set><Key>{}</Key><Value>{}</Value></Set>",
+                        escape_xml(key),
+                        escape_xml(value)
+                    );
+                }
+                xml += "</MirrorHeaders>";
+            }
+        }
+        xml += &optional("Protocol", redirect.protocol.as_deref().map(escape_xml));
+        xml += &optional("HostName", redirect.host_name.as_deref().map(escape_xml));
+        xml += &optional(
+            "ReplaceKeyPrefixWith",
+            redirect.replace_key_prefix_with.as_deref().map(escape_xml),
+        );
+        xml += &optional(
+            "ReplaceKeyWith",
+            redirect.replace_key_with.as_deref().map(escape_xml),
+        );
+        xml += &optional("EnableReplacePrefix", redirect.enable_replace_prefix);
+        xml += &optional("HttpRedirectCode", redirect.http_redirect_code);
+        xml + "</Redirect></RoutingRule>"
+    }
+}
+
+/// Elements read by `read_leaf`, anything else unknown is skipped whole.
+const LEAVES: &[&[u8]] = &[
+    b"Suffix",
+    b"SupportSubDir",
+    b"Type",
+    b"Key",
+    b"HttpStatus",
+    b"Equals",
+    b"Value",
+    b"PassAll",
+    b"Pass",
+    b"Remove",
+    b"MirrorURL",
+    b"MirrorPassQueryString",
+    b"MirrorFollowRedirect",
+    b"MirrorCheckMd5",
+    b"RuleNumber",
+    b"KeyPrefixEquals",
+    b"KeySuffixEquals",
+    b"HttpErrorCodeReturnedEquals",
+    b"RedirectType",
+    b"PassQueryString",
+    b"ReplaceKeyWith",
+    b"ReplaceKeyPrefixWith",
+    b"EnableReplacePrefix",
+    b"Protocol",
+    b"HostName",
+    b"HttpRedirectCode",
+];
+
+/// Stores the text of the leaf element `name`, inside container `parent`.
+fn read_leaf(
+    config: &mut WebsiteConfiguration,
+    rule: &mut Option<RoutingRule>,
+    header: &mut (String, String),
+    parent: &[u8],
+    name: &[u8],
+    text: String,
+) -> Result<(), Error> {
+    match (parent, name) {
+        (b"IndexDocument", _) => {
+            let index = config
+                .index_document
+                .get_or_insert_with(|| IndexDocument::new(""));
+            match name {
+                b"Suffix" => index.suffix = text,
+                b"SupportSubDir" => index.support_sub_dir = parse(text)?,
+                b"Type" => index.sub_dir_type = text.parse()?,
+                _ => (),
+            }
+        }
+        (b"ErrorDocument", b"Key") => {
+            if let Some(error) = config.error_document.as_mut() {
+                error.key = text
+            }
+        }
+        (b"ErrorDocument", b"HttpStatus") => {
+            if let Some(error) = config.error_document.as_mut() {
+                error.http_status = Some(parse(text)?)
+            }
+        }
+        (b"IncludeHeader", b"Key") | (b"Set", b"Key") => header.0 = text,
+        (b"IncludeHeader", b"Equals") | (b"Set", b"Value") => header.1 = text,
+        (b"MirrorHeaders", b"PassAll") => mirror_headers(rule).pass_all = parse(text)?,
+        (b"MirrorHeaders", b"Pass") => mirror_headers(rule).pass.push(text),
+        (b"MirrorHeaders", b"Remove") => mirror_headers(rule).remove.push(text),
+        (_, b"MirrorURL") => mirror(rule).url = text,
+        (_, b"MirrorPassQueryString") => mirror(rule).pass_query_string = Some(parse(text)?),
+        (_, b"MirrorFollowRedirect") => mirror(rule).follow_redirect = Some(parse(text)?),
+        (_, b"MirrorCheckMd5") => mirror(rule).check_md5 = Some(parse(text)?),
+        _ => {
+            let rule = match rule.as_mut() {
+                Some(rule) => rule,
+                None => return Ok(()),
+            };
+            let (condition, redirect) = (&mut rule.condition, &mut rule.redirect);
+            match name {
+                b"RuleNumber" => rule.rule_number = parse(text)?,
+                b"KeyPrefixEquals" => condition.key_prefix_equals = Some(text),
+                b"KeySuffixEquals" => condition.key_suffix_equals = Some(text),
+                b"HttpErrorCodeReturnedEquals" => {
+                    condition.http_error_code_returned_equals = Some(parse(text)?)
+                }
+                b"RedirectType" => redirect.redirect_type = text.parse()?,
+                b"PassQueryString" => redirect.pass_query_string = Some(parse(text)?),
+                b"ReplaceKeyWith" => redirect.replace_key_with = Some(text),
+                b"ReplaceKeyPrefixWith" => redirect.replace_key_prefix_with = Some(text),
+                b"EnableReplacePrefix" => redirect.enable_replace_prefix = Some(parse(text)?),
+                b"Protocol" => redirect.protocol = Some(text),
+                b"HostName" => redirect.host_name = Some(text),
+                b"HttpRedirectCode" => redirect.http_redirect_code = Some(parse(text)?),
+                _ => (),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Mirror settings of the rule being read, created on first use.
+fn mirror(rule: &mut Option<RoutingRule>) -> &mut MirrorConfig {
+    let rule = rule.get_or_insert_with(|| RoutingRule {
+        rule_number: 0,
+        condition: RoutingCondition::default(),
+        redirect: RoutingRedirect::new(RedirectType::Mirror),
+    });
+    rule.redirect.mirror.get_or_insert_with(Default::default)
+}
+
+fn mirror_headers(rule: &mut Option<RoutingRule>) -> &mut MirrorHeaders {
+    mirror(rule).headers.get_or_insert_with(Default::default)
+}
+
+fn optional<T: fmt::Display>(name: &str, value: Option<T>) -> String {
+    match value {
+        Some(value) => format!("<{}>{}</{}>", name, value, name),
+        None => String::new(),
+    }
+}
+
+fn parse<T: FromStr>(text: String) -> Result<T, Error> {
+    text.parse()
+        .map_err(|_| website_error(format!("invalid value in website configuration: {}", text)))
+}
+
+fn website_error(msg: String) -> Error {
+    Error::Bucket(BucketError::GetError { msg })
+}
@@ -0,0 +1,84 @@
+use oss_rust_sdk::prelude::*;
+
+fn oss_instance(endpoint: &str) -> OSS<'_> {
+    OSS::new("id", "secret", endpoint, "bucket")
+}
+
+#[test]
+fn detect_addressing() {
+    let cases = [
+        ("oss-cn-hangzhou.aliyuncs.com", Addressing::VirtualHosted),
+        (
+            "https://oss-cn-hangzhou.aliyuncs.com",
+            Addressing::VirtualHosted,
+        ),
+        ("http://localhost:9000", Addressing::VirtualHosted),
+        ("10.0.0.1", Addressing::Ip),
+        ("http://10.0.0.1:9000", Addressing::Ip),
+        ("https://[2001:db8::1]:443", Addressing::Ip),
+        ("::1", Addressing::Ip),
+    ];
+    for (endpoint, addressing) in cases.iter() {
+        assert_eq!(Addressing::detect(endpoint), *addressing, "{}", endpoint);
+    }
+}
+
+#[test]
+fn host_per_addressing() {
+    let mut oss = oss_instance("https://oss-cn-hangzhou.aliyuncs.com");
+    assert_eq!(
+        oss.host("bucket", "a/b.txt", "acl"),
+        "https://bucket.oss-cn-hangzhou.aliyuncs.com/a/b.txt?acl"
+    );
+    assert_eq!(
+        oss.host("", "", ""),
+        "https://oss-cn-hangzhou.aliyuncs.com/?"
+    );
+
+    oss.set_addressing(Addressing::PathStyle);
+    assert_eq!(
+        oss.host("bucket", "a/b.txt", ""),
+        "https://oss-cn-hangzhou.aliyuncs.com/bucket/a/b.txt?"
+    );
+
+    let mut oss = oss_instance("static.example.com");
+    oss.set_addressing(Addressing::Cname);
+    assert_eq!(
+        oss.host("bucket", "a/b.txt", ""),
+        "http://static.example.com/a/b.txt?"
+    );
+
+    let oss = oss_instance("http://10.0.0.1:9000/");
+    assert_eq!(
+        oss.host("bucket", "key", ""),
+        "http://10.0.0.1:9000/bucket/key?"
+    );
+    let oss = oss_instance("::1");
+    assert_eq!(oss.host("bucket", "key", ""), "http://[::1]/bucket/key?");
+}
+
+#[test]
+fn signature_ignores_addressing() {
+    let mut oss = oss_instance("oss-cn-hangzhou.aliyuncs.com");
+    let virtual_hosted = oss.signiture_url("key", 1_600_000_000, "GET").unwrap();
+    oss.set_addressing(Addressing::PathStyle);
+    let path_style = oss.signiture_url("key", 1_600_000_000, "GET").unwrap();
+
+    let query = |url: &str| url.split_once('?').unwrap().1.to_owned();
+    assert!(path_style.starts_with("http://oss-cn-hangzhou.aliyuncs.com/bucket/key?"));
+    assert_eq!(query(&virtual_hosted), query(&path_style));
+}
+
+#[test]
+fn signed_rtmp_url() {
+    let oss = oss_instance("https://oss-cn-hangzhou.aliyuncs.com");
+    let url = oss
+        .signed_rtmp_url("channel", 1_600_000_000, "live.m3u8")
+        .unwrap();
+    assert_eq!(
+        url,
+        "rtmp://bucket.oss-cn-hangzhou.aliyuncs.com/live/channel?playlistName=live.m3u8&OSSAccessKeyId=id&Expires=1600000000&Signature=0v4PVc8AB8LxOvDoS3eX4X3AhHA%3D"
+    );
+    let url = oss.signed_rtmp_url("channel", 1_600_000_000, None).unwrap();
+    assert!(url.ends_with("/live/channel?OSSAccessKeyId=id&Expires=1600000000&Signature=2qfc5W88oOXvJwEsqKzhQYqhvUU%3D"));
+}
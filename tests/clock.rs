@@ -0,0 +1,78 @@
+use chrono::{TimeZone, Utc};
+use std::sync::Arc;
+
+use oss_rust_sdk::cassette::{parse_cassette, Replayer};
+use oss_rust_sdk::clock::{FixedClock, SkewCorrectedClock};
+use oss_rust_sdk::prelude::*;
+
+const SKEWED: &str = "\
+-> GET https://oss-example.oss-cn-hangzhou.aliyuncs.com/object?
+-> body
+<- 403
+<- date: Mon, 01 Jun 2020 08:15:00 GMT
+<- content-type: application/xml
+<- body PEVycm9yPjxDb2RlPlJlcXVlc3RUaW1lVG9vU2tld2VkPC9Db2RlPjwvRXJyb3I+
+
+-> GET https://oss-example.oss-cn-hangzhou.aliyuncs.com/object?
+-> body
+<- 200
+<- date: Mon, 01 Jun 2020 08:15:00 GMT
+<- body aGVsbG8=
+";
+
+fn oss_instance() -> OSS<'static> {
+    OSS::new(
+        "id",
+        "secret",
+        "https://oss-cn-hangzhou.aliyuncs.com",
+        "oss-example",
+    )
+}
+
+#[test]
+fn fixed_clock_signing() {
+    let mut oss_instance = oss_instance();
+    oss_instance.freeze_time(Utc.with_ymd_and_hms(2020, 6, 1, 8, 0, 0).unwrap());
+    assert_eq!(oss_instance.date(), "Mon, 01 Jun 2020 08:00:00 GMT");
+
+    let url = oss_instance.signiture_url("object", None, "GET").unwrap();
+    assert!(url.contains("Expires=1591002000"), "{}", url);
+    assert_eq!(
+        url,
+        oss_instance.signiture_url("object", None, "GET").unwrap()
+    );
+}
+
+#[test]
+fn skew_is_corrected_and_retried() {
+    let mut oss_instance = oss_instance();
+    let clock = Arc::new(SkewCorrectedClock::new(FixedClock(
+        Utc.with_ymd_and_hms(2020, 6, 1, 8, 0, 0).unwrap(),
+    )));
+    oss_instance.set_clock(clock.clone());
+    let replayer = Arc::new(Replayer::new(parse_cassette(SKEWED).unwrap()));
+    oss_instance.set_transport(replayer.clone());
+
+    let ret = oss_instance.get_with_options("object", NULL_META, &GetOptions::default());
+    assert!(matches!(ret, Ok(ConditionalGetResponse::Full(_))));
+    assert_eq!(replayer.remaining(), 0);
+    assert_eq!(clock.offset().num_minutes(), 15);
+    assert_eq!(oss_instance.date(), "Mon, 01 Jun 2020 08:15:00 GMT");
+}
+
+#[test]
+fn skew_is_retried_for_plain_calls() {
+    let mut oss_instance = oss_instance();
+    let clock = Arc::new(SkewCorrectedClock::new(FixedClock(
+        Utc.with_ymd_and_hms(2020, 6, 1, 8, 0, 0).unwrap(),
+    )));
+    oss_instance.set_clock(clock);
+    let cassette = format!("{}\n{}", SKEWED, SKEWED.replace("-> GET", "-> DELETE"));
+    let replayer = Arc::new(Replayer::new(parse_cassette(&cassette).unwrap()));
+    oss_instance.set_transport(replayer.clone());
+
+    let ret = oss_instance.get_as_buffer("object", NULL_META, None);
+    assert_eq!(ret.unwrap().content, b"hello");
+    assert!(oss_instance.del("object").is_ok());
+    assert_eq!(replayer.remaining(), 0);
+}
@@ -0,0 +1,58 @@
+use oss_rust_sdk::prelude::*;
+
+/// Keys that need escaping in a url, with their encoded path.
+const KEYS: &[(&str, &str)] = &[
+    ("plain.txt", "plain.txt"),
+    ("dir/sub/file", "dir/sub/file"),
+    ("with space", "with%20space"),
+    ("a+b", "a%2Bb"),
+    ("hash#tag", "hash%23tag"),
+    ("what?", "what%3F"),
+    ("100%", "100%25"),
+    ("a&b=c", "a%26b%3Dc"),
+    ("~unreserved-_.", "~unreserved-_."),
+    ("中文/文件.txt", "%E4%B8%AD%E6%96%87/%E6%96%87%E4%BB%B6.txt"),
+    ("dir//double", "dir//double"),
+];
+
+#[test]
+fn object_key_encoding() {
+    let oss = OSS::new("id", "secret", "oss-cn-hangzhou.aliyuncs.com", "bucket");
+    for (key, encoded) in KEYS {
+        assert_eq!(
+            oss.host("bucket", key, ""),
+            format!("http://bucket.oss-cn-hangzhou.aliyuncs.com/{}?", encoded),
+            "{}",
+            key
+        );
+    }
+}
+
+#[test]
+fn list_params_encoding() {
+    let opts = ListOptions::new(
+        "a b/+#".to_string(),
+        "token=/+".to_string(),
+        "/".to_string(),
+        10,
+    );
+    let (params, resources) = OSS::get_list_2_params_str(&opts);
+    assert_eq!(
+        params,
+        "list-type=2&continuation-token=token%3D%2F%2B&delimiter=%2F&max-keys=10&prefix=a%20b%2F%2B%23"
+    );
+    // signatures cover the unencoded value
+    assert_eq!(resources, "continuation-token=token=/+");
+}
+
+#[test]
+fn presigned_url_is_encoded() {
+    let oss = OSS::new("id", "secret", "oss-cn-hangzhou.aliyuncs.com", "bucket");
+    for (key, encoded) in KEYS {
+        let url = oss.signiture_url(key, 1_600_000_000, "GET").unwrap();
+        let (path, query) = url.split_once('?').unwrap();
+        assert!(path.ends_with(encoded), "{}", url);
+        let signature = query.rsplit_once("Signature=").unwrap().1;
+        assert!(!signature.contains(&['+', '/', '='][..]), "{}", url);
+    }
+}
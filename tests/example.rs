@@ -84,6 +84,107 @@ fn smoke_test() {
     }
 }
 
+#[test]
+fn symlink_test() {
+    let oss_instance = default_oss();
+    let link = "rust_oss_sdk_test_latest";
+
+    let ret = oss_instance.put(BUF, FILE_NAME, None);
+    assert!(ret.is_ok());
+    let ret = oss_instance.put_symlink(link, FILE_NAME, None);
+    assert!(ret.is_ok());
+    let ret = oss_instance.get_symlink(link);
+    assert!(ret.is_ok() && ret.unwrap() == FILE_NAME);
+    // reading through the link returns the target's data
+    let ret = oss_instance.get_as_buffer(link, NULL_META, None);
+    assert!(ret.is_ok() && ret.unwrap().content == BUF);
+
+    assert!(oss_instance.del(link).is_ok());
+    assert!(oss_instance.del(FILE_NAME).is_ok());
+}
+
+#[test]
+fn copy_and_tagging_test() {
+    let oss_instance = default_oss();
+    let copy_name = "rust_oss_sdk_test_copy";
+
+    assert!(oss_instance.put(BUF, FILE_NAME, None).is_ok());
+    let ret = oss_instance.copy_object(FILE_NAME, copy_name, None);
+    assert!(ret.is_ok() && !ret.unwrap().e_tag.is_empty());
+
+    let mut tags = HashMap::new();
+    tags.insert("project", "oss-rust-sdk");
+    assert!(oss_instance
+        .put_object_tagging(copy_name, tags, None)
+        .is_ok());
+    let ret = oss_instance.get_object_tagging(copy_name, None);
+    assert!(ret.is_ok() && ret.unwrap().get("project").map(String::as_str) == Some("oss-rust-sdk"));
+    assert!(oss_instance.delete_object_tagging(copy_name, None).is_ok());
+
+    // versions are listed even when versioning was never enabled, with a "null" id
+    let opts = ListVersionsOptions {
+        prefix: copy_name.to_string(),
+        ..Default::default()
+    };
+    let ret = oss_instance.list_object_versions(&opts);
+    assert!(ret.is_ok() && ret.unwrap().versions.len() == 1);
+
+    assert!(oss_instance.del(copy_name).is_ok());
+    assert!(oss_instance.del(FILE_NAME).is_ok());
+}
+
+#[test]
+fn conditional_get_test() {
+    let oss_instance = default_oss();
+    assert!(oss_instance.put(BUF, FILE_NAME, None).is_ok());
+
+    let opts = GetOptions {
+        range: Some(ByteRange::FromTo(0, 3)),
+        ..Default::default()
+    };
+    let ret = oss_instance.get_with_options(FILE_NAME, NULL_META, &opts);
+    let etag = match ret {
+        Ok(ConditionalGetResponse::Partial {
+            content_range,
+            response,
+        }) => {
+            assert_eq!(content_range.total, BUF.len() as u64);
+            assert_eq!(response.content, &BUF[..4]);
+            response.headers["etag"].to_str().unwrap().to_string()
+        }
+        _ => panic!("expected a partial response"),
+    };
+
+    let opts = GetOptions {
+        if_none_match: Some(etag),
+        ..Default::default()
+    };
+    let ret = oss_instance.get_with_options(FILE_NAME, NULL_META, &opts);
+    assert!(matches!(ret, Ok(ConditionalGetResponse::NotModified { .. })));
+
+    assert!(oss_instance.del(FILE_NAME).is_ok());
+}
+
+#[test]
+fn download_file_test() {
+    let oss_instance = default_oss();
+    let content: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+    assert!(oss_instance.put(&content, FILE_NAME, None).is_ok());
+
+    let path = std::env::temp_dir().join("rust_oss_sdk_download_test");
+    let opts = DownloadOptions {
+        part_size: 16 * 1024,
+        parallel: 4,
+        ..Default::default()
+    };
+    let ret = oss_instance.download_file(FILE_NAME, &path, &opts);
+    assert!(ret.is_ok());
+    assert_eq!(std::fs::read(&path).unwrap(), content);
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(oss_instance.del(FILE_NAME).is_ok());
+}
+
 #[test]
 fn host_test() {
     let oss_instance = default_oss();
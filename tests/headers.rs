@@ -0,0 +1,91 @@
+use chrono::{TimeZone, Utc};
+use oss_rust_sdk::prelude::*;
+
+#[test]
+fn parse_restore_status() {
+    let ret = "ongoing-request=\"true\"".parse::<RestoreStatus>();
+    assert_eq!(ret.unwrap(), RestoreStatus::Ongoing);
+
+    let ret = "ongoing-request=\"false\", expiry-date=\"Sun, 16 Apr 2017 08:12:33 GMT\""
+        .parse::<RestoreStatus>();
+    assert_eq!(
+        ret.unwrap(),
+        RestoreStatus::Restored {
+            expiry_date: Utc.with_ymd_and_hms(2017, 4, 16, 8, 12, 33).unwrap()
+        }
+    );
+
+    assert!("ongoing-request=\"false\"".parse::<RestoreStatus>().is_err());
+    assert!("garbage".parse::<RestoreStatus>().is_err());
+}
+
+#[test]
+fn parse_content_range() {
+    let ret = "bytes 0-9/443".parse::<ContentRange>();
+    assert_eq!(
+        ret.unwrap(),
+        ContentRange {
+            start: 0,
+            end: 9,
+            total: 443
+        }
+    );
+    assert!("bytes */443".parse::<ContentRange>().is_err());
+    assert!("0-9/443".parse::<ContentRange>().is_err());
+}
+
+#[test]
+fn format_byte_range() {
+    assert_eq!(ByteRange::FromTo(0, 9).to_string(), "bytes=0-9");
+    assert_eq!(ByteRange::From(100).to_string(), "bytes=100-");
+    assert_eq!(ByteRange::Last(500).to_string(), "bytes=-500");
+}
+
+#[test]
+fn crc64_ecma() {
+    use oss_rust_sdk::crc64::{checksum, combine, Crc64};
+
+    assert_eq!(checksum(b""), 0);
+    assert_eq!(checksum(b"123456789"), 0x995D_C9BB_DF19_39FA);
+
+    let mut crc = Crc64::new();
+    crc.update(b"1234");
+    crc.update(b"56789");
+    assert_eq!(crc.finish(), 0x995D_C9BB_DF19_39FA);
+
+    let data = b"123456789";
+    for split in 0..=data.len() {
+        let (a, b) = data.split_at(split);
+        assert_eq!(
+            combine(checksum(a), checksum(b), b.len() as u64),
+            0x995D_C9BB_DF19_39FA
+        );
+    }
+}
+
+#[test]
+fn mime_types() {
+    use oss_rust_sdk::mime::MimeTypes;
+
+    let mut mime_types = MimeTypes::new();
+    assert_eq!(mime_types.guess("a/b/c.tar.GZ"), Some("application/gzip"));
+    assert_eq!(
+        mime_types.guess("7z.dir/archive.7z"),
+        Some("application/x-7z-compressed")
+    );
+    assert_eq!(mime_types.guess("index.htm"), Some("text/html"));
+    assert_eq!(mime_types.guess("font.woff2"), Some("font/woff2"));
+    assert_eq!(mime_types.guess("a.dir/README"), None);
+    assert_eq!(mime_types.guess("data.unknown"), None);
+
+    mime_types.insert("UNKNOWN", "application/x-unknown");
+    mime_types.insert("txt", "text/plain; charset=utf-8");
+    assert_eq!(
+        mime_types.guess("data.unknown"),
+        Some("application/x-unknown")
+    );
+    assert_eq!(
+        mime_types.guess("notes.txt"),
+        Some("text/plain; charset=utf-8")
+    );
+}
@@ -0,0 +1,31 @@
+use oss_rust_sdk::prelude::*;
+
+#[test]
+fn image_process_string() {
+    let process = ImageProcess::new()
+        .resize(ResizeMode::Lfit, 100, None)
+        .crop(10, 20, 300, 200, Gravity::Center)
+        .rotate(90)
+        .format(ImageFormat::Webp)
+        .quality(80);
+    assert_eq!(
+        process.to_string(),
+        "image/resize,m_lfit,w_100/crop,x_10,y_20,w_300,h_200,g_center/rotate,90/format,webp/quality,q_80"
+    );
+    assert_eq!(ImageProcess::new().to_string(), "image");
+}
+
+#[test]
+fn watermarks_are_base64url() {
+    let text = Watermark::text("Hello 你好?>")
+        .gravity(Gravity::SouthEast)
+        .offset(10, 5)
+        .color("FF0000")
+        .size(40);
+    let image = Watermark::image("logo/panda.png").transparency(50);
+    let process = ImageProcess::new().watermark(text).watermark(image);
+    assert_eq!(
+        process.to_string(),
+        "image/watermark,text_SGVsbG8g5L2g5aW9Pz4,g_se,x_10,y_5,color_FF0000,size_40/watermark,image_bG9nby9wYW5kYS5wbmc,t_50"
+    );
+}
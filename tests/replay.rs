@@ -0,0 +1,149 @@
+use chrono::{TimeZone, Utc};
+use std::sync::Arc;
+
+use oss_rust_sdk::cassette::{Interaction, Replayer};
+use oss_rust_sdk::errors::{Error, ObjectError};
+use oss_rust_sdk::prelude::*;
+
+fn replay(cassette: &str) -> (OSS<'static>, Arc<Replayer>) {
+    let path = format!(
+        "{}/tests/cassettes/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        cassette
+    );
+    let replayer = Arc::new(Replayer::load(path).unwrap());
+    let mut oss_instance = OSS::new(
+        "id",
+        "secret",
+        "https://oss-cn-hangzhou.aliyuncs.com",
+        "oss-example",
+    );
+    oss_instance.set_transport(replayer.clone());
+    oss_instance.freeze_time(Utc.with_ymd_and_hms(2020, 6, 1, 8, 0, 0).unwrap());
+    (oss_instance, replayer)
+}
+
+#[test]
+fn list_details_replay() {
+    let (oss_instance, replayer) = replay("list_details.cassette");
+    let opts = ListOptions::new("fun/".to_string(), None, "/".to_string(), 2);
+    let ret = oss_instance.list_details(&opts).unwrap();
+    assert_eq!(replayer.remaining(), 0);
+
+    assert!(ret.is_truncated);
+    assert_eq!(ret.next_marker, "CgJmdW4vdGVzdC5qcGc-");
+    assert_eq!(ret.prefixes, vec!["fun/2019/", "fun/2020/"]);
+    let keys: Vec<&str> = ret.objects.iter().map(|o| o.key()).collect();
+    assert_eq!(keys, vec!["fun/movie.avi", "fun/test.jpg"]);
+    assert_eq!(ret.objects[0].size(), "344606");
+    assert_eq!(ret.objects[1].last_modified(), "2020-05-31T02:12:09.000Z");
+}
+
+#[test]
+fn list_bucket_replay() {
+    let (oss_instance, replayer) = replay("list_bucket.cassette");
+    let ret = oss_instance
+        .list_bucket(None::<std::collections::HashMap<&str, Option<&str>>>)
+        .unwrap();
+    assert_eq!(replayer.remaining(), 0);
+
+    assert_eq!(ret.id(), "51264");
+    let names: Vec<&str> = ret.buckets().iter().map(|b| b.name()).collect();
+    assert_eq!(names, vec!["app-base-oss", "mybucket"]);
+    assert_eq!(ret.buckets()[1].location(), "oss-cn-hangzhou");
+    assert_eq!(ret.buckets()[1].storage_class(), "IA");
+}
+
+#[test]
+fn list_versions_replay() {
+    let (oss_instance, replayer) = replay("list_versions.cassette");
+    let opts = ListVersionsOptions {
+        prefix: "fun/".to_string(),
+        delimiter: "/".to_string(),
+        max_keys: "3".to_string(),
+        ..Default::default()
+    };
+    let ret = oss_instance.list_object_versions(&opts).unwrap();
+    assert!(ret.is_truncated);
+    assert_eq!(ret.next_key_marker, "fun/test.jpg");
+    assert_eq!(ret.prefixes, vec!["fun/2020/"]);
+    let versions: Vec<(&str, bool)> = ret
+        .versions
+        .iter()
+        .map(|v| (v.key.as_str(), v.is_latest))
+        .collect();
+    assert_eq!(
+        versions,
+        vec![("fun/movie.avi", false), ("fun/test.jpg", true)]
+    );
+    assert_eq!(ret.versions[1].size, "27");
+    assert_eq!(ret.versions[1].storage_class, "IA");
+
+    assert_eq!(ret.delete_markers.len(), 1);
+    let marker = &ret.delete_markers[0];
+    assert_eq!(marker.key, "fun/movie.avi");
+    assert!(marker.is_latest);
+    assert!(marker.e_tag.is_empty() && marker.size.is_empty());
+
+    let ret = oss_instance
+        .del_version("fun/test.jpg", &ret.versions[1].version_id)
+        .unwrap();
+    assert!(!ret.delete_marker);
+    let ret = oss_instance
+        .del_version("fun/movie.avi", &marker.version_id)
+        .unwrap();
+    assert!(ret.delete_marker);
+    assert_eq!(ret.version_id.as_ref(), Some(&marker.version_id));
+    assert_eq!(replayer.remaining(), 0);
+}
+
+#[test]
+fn put_and_head_version_replay() {
+    let (oss_instance, replayer) = replay("put_version.cassette");
+    let version = "CAEQNRiBgMClj7qD0BYiIDQ5Y2QyMjc3NGZkODRlMTU5M2VkY2U3MWRiNGRh";
+    let ret = oss_instance.put(b"hello", "fun/hello.txt", None).unwrap();
+    assert_eq!(ret.e_tag, "\"5D41402ABC4B2A76B9719D911017C592\"");
+    assert_eq!(ret.version_id.as_deref(), Some(version));
+
+    let meta = oss_instance.head_version("fun/hello.txt", version).unwrap();
+    assert_eq!(replayer.remaining(), 0);
+    assert_eq!(meta.content_length, 5);
+    assert_eq!(meta.version_id.as_deref(), Some(version));
+    assert_eq!(meta.meta.get("author").map(String::as_str), Some("oss"));
+}
+
+#[test]
+fn truncated_list_is_an_error() {
+    let (mut oss_instance, _) = replay("list_bucket.cassette");
+    let truncated = b"<ListBucketResult><IsTruncated>false</IsTruncated><CommonPrefixes><Prefix>fun/2019/</Prefix>";
+    oss_instance.set_transport(Arc::new(Replayer::new(vec![Interaction {
+        method: "GET".to_string(),
+        url: "https://oss-example.oss-cn-hangzhou.aliyuncs.com/?list-type=2".to_string(),
+        status: 200,
+        response_body: truncated.to_vec(),
+        ..Default::default()
+    }])));
+    assert!(oss_instance.list_details(None).is_err());
+}
+
+#[test]
+fn malformed_list_is_an_error() {
+    let (oss_instance, replayer) = replay("malformed_list.cassette");
+    assert!(oss_instance
+        .list_bucket(None::<std::collections::HashMap<&str, Option<&str>>>)
+        .is_err());
+    assert!(oss_instance.list_objects(None).is_err());
+    match oss_instance.list_objects(None) {
+        Err(Error::Object(ObjectError::GetError { msg })) => assert!(msg.contains("403")),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(replayer.remaining(), 0);
+}
+
+#[test]
+fn unexpected_request_is_rejected() {
+    let (oss_instance, _) = replay("list_bucket.cassette");
+    assert!(oss_instance
+        .get_as_buffer("object", NULL_META, None)
+        .is_err());
+}